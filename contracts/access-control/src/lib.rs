@@ -1,11 +1,44 @@
 #![no_std]
+// Grants carry their full terms as entry point arguments
+#![allow(clippy::too_many_arguments)]
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Env, String, Vec,
+    contract, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Map, String, Symbol,
+    Val, Vec,
 };
 
 mod test;
 
+/// How long break-glass access lasts before it lapses on its own
+const BREAK_GLASS_PERIOD: u64 = 4 * 3600;
+
+/// Longest resource id or resource pattern the contract will match against.
+/// Resource ids are '/'-separated paths; a trailing "/*" covers every descendant.
+const MAX_RESOURCE_ID_LEN: usize = 256;
+
+/// Most grants a single call stores or revokes. Each grant takes about a dozen ledger
+/// writes (the permission plus its place in four indexes), and a transaction may write at
+/// most 50 ledger entries.
+const MAX_BATCH_SIZE: u32 = 4;
+
+/// Most roles a single entity may hold; `check_access` walks all of them
+const MAX_ENTITY_ROLES: u32 = 10;
+
+/// Most rules a single role may carry
+const MAX_ROLE_RULES: u32 = 20;
+
+/// Most resources that may be carved out of one entity's wildcard grants
+const MAX_EXCLUSIONS: u32 = 20;
+
+/// Most grants that may be re-shared directly from one permission
+const MAX_DELEGATIONS: u32 = 20;
+
+/// Most entries returned by a single page of a listing
+const MAX_PAGE_SIZE: u32 = 100;
+
+/// Length of the day that access windows repeat over
+const SECONDS_PER_DAY: u32 = 86_400;
+
 /// --------------------
 /// Entity Types
 /// --------------------
@@ -29,6 +62,21 @@ pub struct EntityData {
     pub name: String,
     pub metadata: String,
     pub active: bool,
+    pub deactivated_at: u64, // Most recent deactivation, 0 if never deactivated
+    pub deactivation_reason: String,
+}
+
+/// --------------------
+/// Attestation
+/// --------------------
+/// A trusted attester's confirmation of an entity's declared type
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Attestation {
+    pub entity_type: EntityType,
+    pub attester: Address,
+    pub evidence_hash: BytesN<32>, // Hash of the off-chain evidence, e.g. a license record
+    pub attested_at: u64,
 }
 
 /// --------------------
@@ -37,6 +85,229 @@ pub struct EntityData {
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AccessPermission {
+    pub resource_id: String,
+    pub level: AccessAction, // Highest action allowed; each level includes those below it
+    pub purpose: Purpose,    // The only purpose of use this consent covers
+    pub granted_by: Address,
+    pub granted_at: u64,
+    pub expires_at: u64,                 // 0 means no expiration
+    pub delegation_depth: u32,           // Further re-share hops allowed; 0 means none
+    pub parent_resource: Option<String>, // Grantor's permission this was re-shared from
+    pub not_before: u64,                 // Access starts at this time; 0 means immediately
+    pub windows: Vec<AccessWindow>,      // Recurring on-duty windows; empty means any time
+}
+
+/// --------------------
+/// Access Window
+/// --------------------
+/// A weekly recurring period, in UTC ledger time, during which a grant may be used
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccessWindow {
+    pub days: u32,  // Bitmask of weekdays, bit 0 = Monday .. bit 6 = Sunday
+    pub start: u32, // Seconds after midnight the window opens
+    pub end: u32,   // Seconds after midnight it closes; before `start` runs past midnight
+}
+
+/// --------------------
+/// Purpose of Use
+/// --------------------
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Purpose {
+    Treatment,
+    Payment,
+    Operations,
+    Research,
+    PublicHealth,
+}
+
+/// A permission together with the entity holding it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Consent {
+    pub grantee: Address,
+    pub permission: AccessPermission,
+}
+
+/// --------------------
+/// Grant Reference
+/// --------------------
+/// Points at one grantee's permission on a resource
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrantRef {
+    pub grantee: Address,
+    pub resource_id: String,
+}
+
+/// Secondary indexes over stored permissions, each a paginated list of `GrantRef`s
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GrantIndex {
+    Holder(Address),                  // Permissions held by an entity
+    Resource(String),                 // Permissions on exactly one resource id or pattern
+    GrantorPurpose(Address, Purpose), // Permissions granted by an entity for one purpose
+    Subtree(String, Purpose),         // Permissions for one purpose under a registered resource
+}
+
+/// --------------------
+/// Batch Operations
+/// --------------------
+/// One grant in a batch; level and purpose are shared by the whole batch
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrantRequest {
+    pub grantee: Address,
+    pub resource_id: String,
+    pub expires_at: u64, // 0 means no expiration
+}
+
+/// Per-item result of a batch; a batch is applied only if every item is valid
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchOutcome {
+    Applied,
+    NotApplied, // Valid, but another item in the batch was rejected
+    NotRegistered,
+    Deactivated,
+    InvalidResource,
+    NotAuthorized,
+    AlreadyGranted,
+    NotFound,
+    Duplicate,       // Repeats an earlier item in the same batch
+    InvalidSchedule, // Would expire before it becomes usable
+}
+
+/// --------------------
+/// Resource Ownership
+/// --------------------
+/// Registered owner of a resource, or of a whole subtree when the id ends in "/*"
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResourceRecord {
+    pub resource_id: String,
+    pub owner: Address,
+    pub co_owners: Vec<Address>, // e.g. guardians, who may also grant and revoke
+    pub registered_at: u64,
+}
+
+/// --------------------
+/// Access Exclusion
+/// --------------------
+/// Carves a narrower resource out of a wildcard grant
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccessExclusion {
+    pub resource_id: String,
+    pub excluded_by: Address,
+    pub excluded_at: u64,
+}
+
+/// --------------------
+/// Access Actions
+/// --------------------
+/// Ordered from least to most privileged
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum AccessAction {
+    Read,
+    Append,
+    Update,
+    Share,
+}
+
+/// --------------------
+/// Roles
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleRule {
+    pub resource_pattern: String, // Exact id, or a path ending in "/*"
+    pub actions: Vec<AccessAction>,
+    pub purposes: Vec<Purpose>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleData {
+    pub name: String,
+    pub rules: Vec<RoleRule>,
+    pub updated_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleAssignment {
+    pub scope: Address, // Entity that defined the role and assigned it
+    pub role: String,
+    pub assigned_at: u64,
+}
+
+/// --------------------
+/// Break-Glass Access
+/// --------------------
+/// Immutable record of an emergency access taken without prior consent
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyAccess {
+    pub id: u64,
+    pub doctor: Address,
+    pub owner: Address, // Whose records were opened
+    pub resource_id: String,
+    pub justification: String,
+    pub accessed_at: u64,
+    pub expires_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReviewOutcome {
+    Approved,
+    Flagged, // Unjustified; any remaining emergency access is cut off
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyReview {
+    pub outcome: ReviewOutcome,
+    pub reviewer: Address,
+    pub notes: String,
+    pub reviewed_at: u64,
+}
+
+/// --------------------
+/// Access Log
+/// --------------------
+/// One recorded attempt to use a resource
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AccessLogEntry {
+    pub accessor: Address,
+    pub resource_id: String, // The resource actually accessed, which may sit inside a subtree
+    pub action: AccessAction,
+    pub purpose: Purpose,
+    pub allowed: bool,
+    pub accessed_at: u64,
+}
+
+/// --------------------
+/// Legacy Layouts
+/// --------------------
+/// Entity record as stored before deactivation details were tracked
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyEntityData {
+    pub entity_type: EntityType,
+    pub name: String,
+    pub metadata: String,
+    pub active: bool,
+}
+
+/// Permission as stored in the legacy `AccessList` vectors, before levels and purposes
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyAccessPermission {
     pub resource_id: String,
     pub granted_by: Address,
     pub granted_at: u64,
@@ -50,8 +321,27 @@ pub struct AccessPermission {
 pub enum DataKey {
     Admin,
     Entity(Address),
-    AccessList(Address),              // Entity -> Vec<AccessPermission>
-    ResourceAccess(String),           // Resource -> Vec<Address> (authorized parties)
+    AccessList(Address),              // Legacy: Entity -> Vec<LegacyAccessPermission>
+    ResourceAccess(String),           // Legacy: Resource -> Vec<Address> (authorized parties)
+    Role(Address, String),            // (Scope, role name) -> RoleData
+    EntityRoles(Address),             // Entity -> Vec<RoleAssignment>
+    Exclusions(Address),              // Entity -> Vec<AccessExclusion>
+    Delegations(Address, String),     // (Holder, resource) -> Vec<GrantRef> re-shared from it
+    EmergencyCounter,
+    EmergencyAccess(u64),
+    EmergencyReview(u64),
+    EmergencyGrant(Address, String),  // (Doctor, resource) -> u64 id of the latest emergency access
+    Resource(String),                 // Resource id or subtree pattern -> ResourceRecord
+    PendingOwner(String),             // Resource -> Address proposed as the next owner
+    Permission(Address, String),      // (Entity, resource) -> AccessPermission
+    IndexLen(GrantIndex),             // Number of grants in an index
+    IndexEntry(GrantIndex, u32),      // (Index, position) -> GrantRef
+    IndexSlot(GrantIndex, Address, String), // (Index, grantee, resource) -> position
+    Attesters,                        // Vec<Address> trusted to verify entity types
+    Attestation(Address),             // Entity -> Attestation of its type
+    AccessLogLen(String),             // Registered resource -> number of log entries
+    AccessLog(String, u32),           // (Registered resource, position) -> AccessLogEntry
+    AdminAssigned(Address),           // Entity given the Admin type by the admin, unlike legacy self-registrations
 }
 
 #[contract]
@@ -80,9 +370,11 @@ impl AccessControl {
     ///
     /// # Arguments
     /// * `wallet` - The wallet address of the entity
-    /// * `entity_type` - The type of entity (Hospital, Doctor, Patient, etc.)
+    /// * `entity_type` - The type of entity (Hospital, Doctor, Patient, etc.); not Admin
     /// * `name` - The name of the entity
     /// * `metadata` - Additional information about the entity
+    ///
+    /// The declared type starts unverified until an attester confirms it.
     pub fn register_entity(
         env: Env,
         wallet: Address,
@@ -92,6 +384,10 @@ impl AccessControl {
     ) {
         wallet.require_auth();
 
+        if entity_type == EntityType::Admin {
+            panic!("Only admin can assign the Admin type");
+        }
+
         let key = DataKey::Entity(wallet.clone());
         if env.storage().persistent().has(&key) {
             panic!("Entity already registered");
@@ -102,278 +398,1828 @@ impl AccessControl {
             name,
             metadata,
             active: true,
+            deactivated_at: 0,
+            deactivation_reason: String::from_str(&env, ""),
         };
 
         env.storage().persistent().set(&key, &entity);
 
-        // Initialize empty access list for the entity
-        let empty_access: Vec<AccessPermission> = Vec::new(&env);
-        env.storage()
-            .persistent()
-            .set(&DataKey::AccessList(wallet.clone()), &empty_access);
-
         env.events().publish(
             (symbol_short!("reg_ent"), wallet),
             symbol_short!("success"),
         );
     }
 
-    /// Grant access permission to an entity for a specific resource
+    /// Register a resource, or a whole subtree ("patient/<addr>/*"), with its owner
+    ///
+    /// Only the admin may register "*" or a top-level subtree such as "ward/*", and only
+    /// the patient whose address it names may register under "patient/<addr>/".
     ///
     /// # Arguments
-    /// * `grantor` - The address granting access (must be authorized)
-    /// * `grantee` - The address receiving access
-    /// * `resource_id` - The identifier of the resource
-    /// * `expires_at` - Expiration timestamp (0 for no expiration)
-    pub fn grant_access(
-        env: Env,
-        grantor: Address,
-        grantee: Address,
-        resource_id: String,
-        expires_at: u64,
-    ) {
-        grantor.require_auth();
+    /// * `owner` - The entity that will own the resource
+    /// * `resource_id` - The identifier of the resource or subtree
+    pub fn register_resource(env: Env, owner: Address, resource_id: String) {
+        owner.require_auth();
 
-        // Verify grantor is a registered entity
-        let grantor_key = DataKey::Entity(grantor.clone());
-        if !env.storage().persistent().has(&grantor_key) {
-            panic!("Grantor not registered");
+        let owner_is_admin = is_admin(&env, &owner);
+        if !owner_is_admin && !is_active(&env, &owner) {
+            panic!("Owner not registered");
         }
+        validate_resource_id(&resource_id);
 
-        // Verify grantee is a registered entity
-        let grantee_key = DataKey::Entity(grantee.clone());
-        if !env.storage().persistent().has(&grantee_key) {
-            panic!("Grantee not registered");
+        let key = DataKey::Resource(resource_id.clone());
+        if env.storage().persistent().has(&key) {
+            panic!("Resource already registered");
+        }
+        // Nothing can be registered inside someone else's subtree
+        if let Some(parent) = find_resource(&env, &resource_id) {
+            if !is_owner(&parent, &owner) {
+                panic!("Resource owned by another entity");
+            }
+        }
+        if !owner_is_admin && !in_own_namespace(&owner, &resource_id) {
+            panic!("Not authorized to register this resource");
         }
 
-        let permission = AccessPermission {
+        let record = ResourceRecord {
             resource_id: resource_id.clone(),
-            granted_by: grantor.clone(),
-            granted_at: env.ledger().timestamp(),
-            expires_at,
+            owner: owner.clone(),
+            co_owners: Vec::new(&env),
+            registered_at: env.ledger().timestamp(),
         };
+        env.storage().persistent().set(&key, &record);
+
+        env.events().publish(
+            (symbol_short!("reg_res"), resource_id),
+            owner,
+        );
+    }
+
+    /// Add a co-owner (e.g. a guardian) who may grant and revoke on the resource
+    ///
+    /// # Arguments
+    /// * `owner` - The primary owner of the resource
+    /// * `resource_id` - The registered resource id
+    /// * `co_owner` - The entity to add
+    pub fn add_co_owner(env: Env, owner: Address, resource_id: String, co_owner: Address) {
+        owner.require_auth();
 
-        // Add permission to grantee's access list
-        let access_key = DataKey::AccessList(grantee.clone());
-        let mut access_list: Vec<AccessPermission> = env
+        let key = DataKey::Resource(resource_id.clone());
+        let mut record: ResourceRecord = env
             .storage()
             .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env));
-
-        // Check if permission already exists for this resource
-        let mut exists = false;
-        for i in 0..access_list.len() {
-            if let Some(existing) = access_list.get(i) {
-                if existing.resource_id == resource_id {
-                    exists = true;
-                    break;
-                }
-            }
+            .get(&key)
+            .expect("Resource not registered");
+        if record.owner != owner {
+            panic!("Only the owner can manage co-owners");
         }
-        if exists {
-            panic!("Access already granted for this resource");
+        if !is_active(&env, &co_owner) {
+            panic!("Co-owner not registered");
         }
+        if is_owner(&record, &co_owner) {
+            panic!("Already an owner");
+        }
+
+        record.co_owners.push_back(co_owner.clone());
+        env.storage().persistent().set(&key, &record);
+
+        env.events().publish(
+            (symbol_short!("co_add"), resource_id),
+            co_owner,
+        );
+    }
 
-        access_list.push_back(permission);
-        env.storage().persistent().set(&access_key, &access_list);
+    /// Remove a co-owner from a resource
+    ///
+    /// # Arguments
+    /// * `owner` - The primary owner of the resource
+    /// * `resource_id` - The registered resource id
+    /// * `co_owner` - The entity to remove
+    pub fn remove_co_owner(env: Env, owner: Address, resource_id: String, co_owner: Address) {
+        owner.require_auth();
 
-        // Add grantee to resource's authorized parties
-        let resource_key = DataKey::ResourceAccess(resource_id.clone());
-        let mut authorized: Vec<Address> = env
+        let key = DataKey::Resource(resource_id.clone());
+        let mut record: ResourceRecord = env
             .storage()
             .persistent()
-            .get(&resource_key)
-            .unwrap_or(Vec::new(&env));
+            .get(&key)
+            .expect("Resource not registered");
+        if record.owner != owner {
+            panic!("Only the owner can manage co-owners");
+        }
 
-        authorized.push_back(grantee.clone());
-        env.storage().persistent().set(&resource_key, &authorized);
+        let index = record
+            .co_owners
+            .first_index_of(&co_owner)
+            .expect("Not a co-owner");
+        record.co_owners.remove(index);
+        env.storage().persistent().set(&key, &record);
 
         env.events().publish(
-            (symbol_short!("grant"), grantee, resource_id),
-            symbol_short!("success"),
+            (symbol_short!("co_rm"), resource_id),
+            co_owner,
         );
     }
 
-    /// Revoke access permission from an entity for a specific resource
+    /// Propose a new owner for a resource; takes effect once they accept
     ///
     /// # Arguments
-    /// * `revoker` - The address revoking access (must be the original grantor or admin)
-    /// * `revokee` - The address losing access
-    /// * `resource_id` - The identifier of the resource
-    pub fn revoke_access(env: Env, revoker: Address, revokee: Address, resource_id: String) {
-        revoker.require_auth();
-
-        // Get admin for authorization check
-        let admin: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Admin)
-            .expect("Contract not initialized");
+    /// * `owner` - The current owner
+    /// * `resource_id` - The registered resource id
+    /// * `new_owner` - The proposed owner
+    pub fn propose_ownership_transfer(
+        env: Env,
+        owner: Address,
+        resource_id: String,
+        new_owner: Address,
+    ) {
+        owner.require_auth();
 
-        // Remove from grantee's access list
-        let access_key = DataKey::AccessList(revokee.clone());
-        let access_list: Vec<AccessPermission> = env
+        let record: ResourceRecord = env
             .storage()
             .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env));
-
-        let mut new_access_list: Vec<AccessPermission> = Vec::new(&env);
-        let mut found = false;
-
-        for i in 0..access_list.len() {
-            if let Some(permission) = access_list.get(i) {
-                if permission.resource_id == resource_id {
-                    // Verify revoker is either the original grantor or admin
-                    if permission.granted_by != revoker && revoker != admin {
-                        panic!("Not authorized to revoke this access");
-                    }
-                    found = true;
-                    // Skip this permission (effectively removing it)
-                } else {
-                    new_access_list.push_back(permission);
-                }
-            }
+            .get(&DataKey::Resource(resource_id.clone()))
+            .expect("Resource not registered");
+        if record.owner != owner {
+            panic!("Only the owner can transfer ownership");
         }
-
-        if !found {
-            panic!("Access permission not found");
+        if !is_active(&env, &new_owner) {
+            panic!("New owner not registered");
         }
 
-        env.storage().persistent().set(&access_key, &new_access_list);
-
-        // Remove from resource's authorized parties
-        let resource_key = DataKey::ResourceAccess(resource_id.clone());
-        let authorized: Vec<Address> = env
-            .storage()
+        env.storage()
             .persistent()
-            .get(&resource_key)
-            .unwrap_or(Vec::new(&env));
-
-        let mut new_authorized: Vec<Address> = Vec::new(&env);
-        for i in 0..authorized.len() {
-            if let Some(addr) = authorized.get(i) {
-                if addr != revokee {
-                    new_authorized.push_back(addr);
-                }
-            }
-        }
-        env.storage().persistent().set(&resource_key, &new_authorized);
+            .set(&DataKey::PendingOwner(resource_id.clone()), &new_owner);
 
         env.events().publish(
-            (symbol_short!("revoke"), revokee, resource_id),
-            symbol_short!("success"),
+            (symbol_short!("own_prop"), resource_id),
+            new_owner,
         );
     }
 
-    /// Check if an entity has access to a specific resource
+    /// Accept a proposed ownership transfer
     ///
     /// # Arguments
-    /// * `entity` - The address to check
-    /// * `resource_id` - The identifier of the resource
-    ///
-    /// # Returns
-    /// `true` if the entity has valid (non-expired) access, `false` otherwise
-    pub fn check_access(env: Env, entity: Address, resource_id: String) -> bool {
-        let access_key = DataKey::AccessList(entity);
-        let access_list: Vec<AccessPermission> = env
+    /// * `new_owner` - The proposed owner
+    /// * `resource_id` - The registered resource id
+    pub fn accept_ownership_transfer(env: Env, new_owner: Address, resource_id: String) {
+        new_owner.require_auth();
+
+        let pending_key = DataKey::PendingOwner(resource_id.clone());
+        let pending: Address = env
             .storage()
             .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env));
-
-        let current_time = env.ledger().timestamp();
+            .get(&pending_key)
+            .expect("No pending ownership transfer");
+        if pending != new_owner {
+            panic!("Not the proposed owner");
+        }
 
-        for i in 0..access_list.len() {
-            if let Some(permission) = access_list.get(i) {
-                if permission.resource_id == resource_id {
-                    // Check if permission is expired
-                    if permission.expires_at == 0 || permission.expires_at > current_time {
-                        return true;
-                    }
-                }
-            }
+        let key = DataKey::Resource(resource_id.clone());
+        let mut record: ResourceRecord = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Resource not registered");
+        let previous_owner = record.owner.clone();
+        record.owner = new_owner.clone();
+        if let Some(index) = record.co_owners.first_index_of(&new_owner) {
+            record.co_owners.remove(index);
         }
+        env.storage().persistent().set(&key, &record);
+        env.storage().persistent().remove(&pending_key);
 
-        false
+        env.events().publish(
+            (symbol_short!("own_xfer"), resource_id),
+            (previous_owner, new_owner),
+        );
     }
 
-    /// Get all entities with access to a specific resource
+    /// Get the registration record of a resource
     ///
     /// # Arguments
     /// * `resource_id` - The identifier of the resource
     ///
     /// # Returns
-    /// A vector of addresses that have access to the resource
-    pub fn get_authorized_parties(env: Env, resource_id: String) -> Vec<Address> {
-        let resource_key = DataKey::ResourceAccess(resource_id);
-        env.storage()
-            .persistent()
-            .get(&resource_key)
-            .unwrap_or(Vec::new(&env))
-    }
-
-    /// Get entity details by wallet address
-    ///
-    /// # Arguments
-    /// * `wallet` - The wallet address of the entity
-    ///
-    /// # Returns
-    /// The EntityData for the given wallet address
-    pub fn get_entity(env: Env, wallet: Address) -> EntityData {
-        let key = DataKey::Entity(wallet);
-        env.storage()
-            .persistent()
-            .get(&key)
-            .expect("Entity not found")
+    /// The exact record, or that of the nearest registered subtree containing it
+    pub fn get_resource(env: Env, resource_id: String) -> ResourceRecord {
+        find_resource(&env, &resource_id).expect("Resource not registered")
     }
 
-    /// Get all access permissions for an entity
+    /// Grant access permission to an entity for a specific resource
     ///
     /// # Arguments
-    /// * `wallet` - The wallet address of the entity
-    ///
+    /// * `grantor` - The address granting access (must be authorized)
+    /// * `grantee` - The address receiving access
+    /// * `resource_id` - The identifier of the resource
+    /// * `level` - The highest action the grantee may perform (Read < Append < Update < Share)
+    /// * `purpose` - The purpose of use the grant is consented for
+    /// * `expires_at` - Expiration timestamp (0 for no expiration)
+    /// * `delegation_depth` - How many further hops the grantee may re-share (requires Share)
+    /// * `not_before` - Time from which the grant may be used (0 for immediately)
+    /// * `windows` - Recurring periods the grant may be used in (empty for any time)
+    pub fn grant_access(
+        env: Env,
+        grantor: Address,
+        grantee: Address,
+        resource_id: String,
+        level: AccessAction,
+        purpose: Purpose,
+        expires_at: u64,
+        delegation_depth: u32,
+        not_before: u64,
+        windows: Vec<AccessWindow>,
+    ) {
+        grantor.require_auth();
+
+        // Verify grantor is a registered entity, or the admin
+        let grantor_is_admin = is_admin(&env, &grantor);
+        let grantor_key = DataKey::Entity(grantor.clone());
+        if !grantor_is_admin && !env.storage().persistent().has(&grantor_key) {
+            panic!("Grantor not registered");
+        }
+
+        // Verify grantee is a registered entity
+        let grantee_key = DataKey::Entity(grantee.clone());
+        if !env.storage().persistent().has(&grantee_key) {
+            panic!("Grantee not registered");
+        }
+
+        if !grantor_is_admin && !is_active(&env, &grantor) {
+            panic!("Grantor is deactivated");
+        }
+        if !is_active(&env, &grantee) {
+            panic!("Grantee is deactivated");
+        }
+
+        validate_resource_id(&resource_id);
+        if !can_manage(&env, &grantor, &resource_id) {
+            panic!("Not authorized to grant access to this resource");
+        }
+        if delegation_depth > 0 && level != AccessAction::Share {
+            panic!("Delegation requires share level");
+        }
+        validate_schedule(expires_at, not_before, &windows);
+
+        let permission = AccessPermission {
+            resource_id: resource_id.clone(),
+            level,
+            purpose,
+            granted_by: grantor.clone(),
+            granted_at: env.ledger().timestamp(),
+            expires_at,
+            delegation_depth,
+            parent_resource: None,
+            not_before,
+            windows,
+        };
+
+        store_permission(&env, &grantee, permission);
+
+        env.events().publish(
+            (symbol_short!("grant"), grantee, resource_id),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Re-share access received with a delegation right
+    ///
+    /// # Arguments
+    /// * `delegator` - The holder of a share-level grant with delegation depth left
+    /// * `grantee` - The address receiving access
+    /// * `resource_id` - The resource, equal to or narrower than the delegator's grant
+    /// * `level` - The highest action the grantee may perform, at most the delegator's level
+    /// * `purpose` - The purpose of use, which the delegator's grant must also be for
+    /// * `expires_at` - Expiration timestamp, no later than the delegator's grant
+    /// * `delegation_depth` - Further hops allowed, less than the delegator's remaining depth
+    pub fn delegate_access(
+        env: Env,
+        delegator: Address,
+        grantee: Address,
+        resource_id: String,
+        level: AccessAction,
+        purpose: Purpose,
+        expires_at: u64,
+        delegation_depth: u32,
+    ) {
+        delegator.require_auth();
+
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Entity(grantee.clone()))
+        {
+            panic!("Grantee not registered");
+        }
+        if !is_active(&env, &grantee) {
+            panic!("Grantee is deactivated");
+        }
+        validate_resource_id(&resource_id);
+
+        if !Self::check_access(
+            env.clone(),
+            delegator.clone(),
+            resource_id.clone(),
+            AccessAction::Share,
+            purpose,
+        ) {
+            panic!("Not authorized to share this resource");
+        }
+
+        // The most specific live grant that lets the delegator re-share this resource
+        let current_time = env.ledger().timestamp();
+        let parent = covering_patterns(&env, &resource_id)
+            .iter()
+            .filter_map(|pattern| find_permission(&env, &delegator, &pattern))
+            .find(|permission| {
+                let live = permission.expires_at == 0 || permission.expires_at > current_time;
+                live && permission.level == AccessAction::Share
+                    && permission.delegation_depth > 0
+                    && permission.purpose == purpose
+            })
+            .expect("No delegation right for this resource");
+
+        if level > parent.level {
+            panic!("Cannot delegate above own level");
+        }
+        if delegation_depth >= parent.delegation_depth {
+            panic!("Delegation depth exceeded");
+        }
+        if delegation_depth > 0 && level != AccessAction::Share {
+            panic!("Delegation requires share level");
+        }
+        if parent.expires_at != 0 && (expires_at == 0 || expires_at > parent.expires_at) {
+            panic!("Delegated access cannot outlive the parent grant");
+        }
+
+        let permission = AccessPermission {
+            resource_id: resource_id.clone(),
+            level,
+            purpose,
+            granted_by: delegator.clone(),
+            granted_at: current_time,
+            expires_at,
+            delegation_depth,
+            parent_resource: Some(parent.resource_id.clone()),
+            // Re-shared access is confined to the delegator's own schedule
+            not_before: parent.not_before,
+            windows: parent.windows.clone(),
+        };
+        // Remember the link so revoking the parent cascades
+        let delegations_key = DataKey::Delegations(delegator.clone(), parent.resource_id);
+        let mut delegations: Vec<GrantRef> = env
+            .storage()
+            .persistent()
+            .get(&delegations_key)
+            .unwrap_or(Vec::new(&env));
+        if delegations.len() >= MAX_DELEGATIONS {
+            panic!("Too many delegations from this grant");
+        }
+        store_permission(&env, &grantee, permission);
+        delegations.push_back(GrantRef {
+            grantee: grantee.clone(),
+            resource_id: resource_id.clone(),
+        });
+        env.storage()
+            .persistent()
+            .set(&delegations_key, &delegations);
+
+        env.events().publish(
+            (symbol_short!("delegate"), grantee, resource_id),
+            delegator,
+        );
+    }
+
+    /// Restrict when an existing grant may be used
+    ///
+    /// # Arguments
+    /// * `caller` - The original grantor, an owner of the resource or the admin
+    /// * `grantee` - The entity holding the grant
+    /// * `resource_id` - The resource the grant is on
+    /// * `not_before` - Time from which the grant may be used (0 for immediately)
+    /// * `windows` - Recurring periods the grant may be used in (empty for any time)
+    pub fn set_access_schedule(
+        env: Env,
+        caller: Address,
+        grantee: Address,
+        resource_id: String,
+        not_before: u64,
+        windows: Vec<AccessWindow>,
+    ) {
+        caller.require_auth();
+
+        let mut permission =
+            find_permission(&env, &grantee, &resource_id).expect("Access permission not found");
+
+        if permission.granted_by != caller && !can_manage(&env, &caller, &resource_id) {
+            panic!("Not authorized to schedule this access");
+        }
+        validate_schedule(permission.expires_at, not_before, &windows);
+
+        // Re-shared access can be narrowed but never scheduled beyond the grant it came from
+        if let Some(parent_resource) = &permission.parent_resource {
+            let parent = find_permission(&env, &permission.granted_by, parent_resource)
+                .expect("Parent grant not found");
+            if !schedule_within(not_before, &windows, &parent) {
+                panic!("Schedule exceeds the parent grant");
+            }
+        }
+
+        permission.not_before = not_before;
+        permission.windows = windows;
+        env.storage().persistent().set(
+            &DataKey::Permission(grantee.clone(), resource_id.clone()),
+            &permission,
+        );
+
+        env.events().publish(
+            (symbol_short!("schedule"), grantee, resource_id),
+            (caller, not_before),
+        );
+    }
+
+    /// List the consents an entity has granted for one purpose of use
+    ///
+    /// # Arguments
+    /// * `grantor` - The entity that granted the consents, typically a patient
+    /// * `purpose` - The purpose of use to report on
+    /// * `start` - Position in the grantor's consents for `purpose` to start from
+    /// * `limit` - How many consents to return, at most `MAX_PAGE_SIZE`
+    pub fn get_consents_by_purpose(
+        env: Env,
+        grantor: Address,
+        purpose: Purpose,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Consent> {
+        consent_page(
+            &env,
+            &GrantIndex::GrantorPurpose(grantor, purpose),
+            start,
+            limit,
+        )
+    }
+
+    /// Revoke up to `limit` of the consents an entity has granted for one purpose of use
+    ///
+    /// # Arguments
+    /// * `grantor` - The entity that granted the consents
+    /// * `purpose` - The purpose of use to withdraw consent for
+    /// * `limit` - How many grants to revoke in this call, at most `MAX_BATCH_SIZE`
+    ///
+    /// # Returns
+    /// The number of grants revoked, not counting grants re-shared from them; call again
+    /// until it returns 0 to withdraw every consent
+    pub fn revoke_consents_by_purpose(
+        env: Env,
+        grantor: Address,
+        purpose: Purpose,
+        limit: u32,
+    ) -> u32 {
+        grantor.require_auth();
+
+        let index = GrantIndex::GrantorPurpose(grantor.clone(), purpose);
+        let revoked = revoke_last(&env, &index, limit);
+
+        env.events().publish(
+            (symbol_short!("rvk_purp"), grantor),
+            (purpose, revoked),
+        );
+        revoked
+    }
+
+    /// List the consents for one purpose of use under a registered resource, whoever granted
+    /// them (the owner, a co-owner, the admin or a delegate)
+    ///
+    /// # Arguments
+    /// * `resource_id` - The registered resource or subtree, e.g. "patient/<address>/*"
+    /// * `purpose` - The purpose of use to report on
+    /// * `start` - Position in the resource's consents for `purpose` to start from
+    /// * `limit` - How many consents to return, at most `MAX_PAGE_SIZE`
+    pub fn get_resource_consents(
+        env: Env,
+        resource_id: String,
+        purpose: Purpose,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Consent> {
+        consent_page(
+            &env,
+            &GrantIndex::Subtree(resource_id, purpose),
+            start,
+            limit,
+        )
+    }
+
+    /// Revoke up to `limit` consents for one purpose of use under a registered resource,
+    /// whoever granted them
+    ///
+    /// # Arguments
+    /// * `caller` - An owner or co-owner of the resource, or the admin
+    /// * `resource_id` - The registered resource or subtree
+    /// * `purpose` - The purpose of use to withdraw consent for
+    /// * `limit` - How many grants to revoke in this call, at most `MAX_BATCH_SIZE`
+    ///
+    /// # Returns
+    /// The number of grants revoked; call again until it returns 0 to withdraw every consent
+    pub fn revoke_resource_consents(
+        env: Env,
+        caller: Address,
+        resource_id: String,
+        purpose: Purpose,
+        limit: u32,
+    ) -> u32 {
+        caller.require_auth();
+
+        let record: ResourceRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Resource(resource_id.clone()))
+            .expect("Resource not registered");
+        if !is_owner(&record, &caller) && !is_admin(&env, &caller) {
+            panic!("Not authorized to revoke these grants");
+        }
+
+        let index = GrantIndex::Subtree(resource_id.clone(), purpose);
+        let revoked = revoke_last(&env, &index, limit);
+
+        env.events().publish(
+            (symbol_short!("rvk_res"), resource_id),
+            (caller, purpose, revoked),
+        );
+        revoked
+    }
+
+    /// Get the grants re-shared from an entity's permission on a resource
+    ///
+    /// # Arguments
+    /// * `holder` - The entity holding the parent permission
+    /// * `resource_id` - The resource of the parent permission
+    pub fn get_delegations(env: Env, holder: Address, resource_id: String) -> Vec<GrantRef> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Delegations(holder, resource_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Revoke access permission from an entity for a specific resource
+    ///
+    /// # Arguments
+    /// * `revoker` - The address revoking access (must be the original grantor or admin)
+    /// * `revokee` - The address losing access
+    /// * `resource_id` - The identifier of the resource
+    pub fn revoke_access(env: Env, revoker: Address, revokee: Address, resource_id: String) {
+        revoker.require_auth();
+
+        // Get admin for authorization check
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("Contract not initialized");
+
+        // Look up the grant on exactly this resource
+        let found = match find_permission(&env, &revokee, &resource_id) {
+            Some(permission) => {
+                // Verify revoker is the original grantor, an owner of the resource or admin
+                if permission.granted_by != revoker
+                    && revoker != admin
+                    && !can_manage(&env, &revoker, &resource_id)
+                {
+                    panic!("Not authorized to revoke this access");
+                }
+                true
+            }
+            None => false,
+        };
+
+        if !found {
+            // No grant on exactly this resource: exclude it from a covering wildcard grant
+            let mut covered = false;
+            for pattern in covering_patterns(&env, &resource_id).iter() {
+                let permission = match find_permission(&env, &revokee, &pattern) {
+                    Some(permission) => permission,
+                    None => continue,
+                };
+                if is_wildcard(&permission.resource_id)
+                    && match_specificity(&permission.resource_id, &resource_id).is_some()
+                {
+                    if permission.granted_by != revoker
+                        && revoker != admin
+                        && !can_manage(&env, &revoker, &resource_id)
+                    {
+                        panic!("Not authorized to revoke this access");
+                    }
+                    covered = true;
+                }
+            }
+            if !covered {
+                panic!("Access permission not found");
+            }
+
+            let exclusions_key = DataKey::Exclusions(revokee.clone());
+            let mut exclusions: Vec<AccessExclusion> = env
+                .storage()
+                .persistent()
+                .get(&exclusions_key)
+                .unwrap_or(Vec::new(&env));
+            if exclusions.len() >= MAX_EXCLUSIONS {
+                panic!("Too many exclusions");
+            }
+            exclusions.push_back(AccessExclusion {
+                resource_id: resource_id.clone(),
+                excluded_by: revoker,
+                excluded_at: env.ledger().timestamp(),
+            });
+            env.storage().persistent().set(&exclusions_key, &exclusions);
+
+            env.events().publish(
+                (symbol_short!("revoke"), revokee, resource_id),
+                symbol_short!("excluded"),
+            );
+            return;
+        }
+
+        remove_permission(&env, &revokee, &resource_id, |_| true);
+        revoke_delegations(&env, &revokee, &resource_id);
+
+        env.events().publish(
+            (symbol_short!("revoke"), revokee, resource_id),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Grant the same level and purpose of access on several (grantee, resource) pairs
+    ///
+    /// # Arguments
+    /// * `grantor` - The address granting access (must be authorized for every resource)
+    /// * `level` - The highest action each grantee may perform
+    /// * `purpose` - The purpose of use the grants are consented for
+    /// * `not_before` - Time from which the grants may be used (0 for immediately)
+    /// * `windows` - Recurring periods the grants may be used in (empty for any time)
+    /// * `items` - The grantees, resources and expiry times to grant
+    ///
+    /// # Returns
+    /// One outcome per item. Nothing is granted unless every item is valid.
+    pub fn batch_grant_access(
+        env: Env,
+        grantor: Address,
+        level: AccessAction,
+        purpose: Purpose,
+        not_before: u64,
+        windows: Vec<AccessWindow>,
+        items: Vec<GrantRequest>,
+    ) -> Vec<BatchOutcome> {
+        grantor.require_auth();
+
+        if items.len() > MAX_BATCH_SIZE {
+            panic!("Batch too large");
+        }
+        validate_schedule(0, not_before, &windows);
+        let grantor_is_admin = is_admin(&env, &grantor);
+        let grantor_key = DataKey::Entity(grantor.clone());
+        if !grantor_is_admin && !env.storage().persistent().has(&grantor_key) {
+            panic!("Grantor not registered");
+        }
+        if !grantor_is_admin && !is_active(&env, &grantor) {
+            panic!("Grantor is deactivated");
+        }
+
+        // Validate everything before writing anything
+        let mut outcomes: Vec<BatchOutcome> = Vec::new(&env);
+        let mut valid = true;
+        for (i, item) in items.iter().enumerate() {
+            let outcome = if !env
+                .storage()
+                .persistent()
+                .has(&DataKey::Entity(item.grantee.clone()))
+            {
+                BatchOutcome::NotRegistered
+            } else if !is_active(&env, &item.grantee) {
+                BatchOutcome::Deactivated
+            } else if !is_valid_resource_id(&item.resource_id) {
+                BatchOutcome::InvalidResource
+            } else if !can_manage(&env, &grantor, &item.resource_id) {
+                BatchOutcome::NotAuthorized
+            } else if find_permission(&env, &item.grantee, &item.resource_id).is_some() {
+                BatchOutcome::AlreadyGranted
+            } else if items.iter().take(i).any(|earlier| {
+                earlier.grantee == item.grantee && earlier.resource_id == item.resource_id
+            }) {
+                BatchOutcome::Duplicate
+            } else if item.expires_at != 0 && not_before >= item.expires_at {
+                BatchOutcome::InvalidSchedule
+            } else {
+                BatchOutcome::Applied
+            };
+            valid &= outcome == BatchOutcome::Applied;
+            outcomes.push_back(outcome);
+        }
+        if !valid {
+            return not_applied(&env, outcomes);
+        }
+
+        let granted_at = env.ledger().timestamp();
+        for item in items.iter() {
+            let permission = AccessPermission {
+                resource_id: item.resource_id.clone(),
+                level,
+                purpose,
+                granted_by: grantor.clone(),
+                granted_at,
+                expires_at: item.expires_at,
+                delegation_depth: 0,
+                parent_resource: None,
+                not_before,
+                windows: windows.clone(),
+            };
+            store_permission(&env, &item.grantee, permission);
+
+            env.events().publish(
+                (symbol_short!("grant"), item.grantee, item.resource_id),
+                symbol_short!("batch"),
+            );
+        }
+        outcomes
+    }
+
+    /// Revoke several grants at once
+    ///
+    /// # Arguments
+    /// * `revoker` - The address revoking access
+    /// * `items` - The grants to revoke, each on exactly the resource it was granted for
+    ///
+    /// # Returns
+    /// One outcome per item. Nothing is revoked unless every item is valid.
+    pub fn batch_revoke_access(
+        env: Env,
+        revoker: Address,
+        items: Vec<GrantRef>,
+    ) -> Vec<BatchOutcome> {
+        revoker.require_auth();
+
+        if items.len() > MAX_BATCH_SIZE {
+            panic!("Batch too large");
+        }
+
+        let mut outcomes: Vec<BatchOutcome> = Vec::new(&env);
+        let mut valid = true;
+        for (i, item) in items.iter().enumerate() {
+            let outcome = match find_permission(&env, &item.grantee, &item.resource_id) {
+                None => BatchOutcome::NotFound,
+                Some(permission)
+                    if permission.granted_by != revoker
+                        && !can_manage(&env, &revoker, &item.resource_id) =>
+                {
+                    BatchOutcome::NotAuthorized
+                }
+                Some(_) if items.iter().take(i).any(|earlier| earlier == item) => {
+                    BatchOutcome::Duplicate
+                }
+                Some(_) => BatchOutcome::Applied,
+            };
+            valid &= outcome == BatchOutcome::Applied;
+            outcomes.push_back(outcome);
+        }
+        if !valid {
+            return not_applied(&env, outcomes);
+        }
+
+        for item in items.iter() {
+            // An earlier item's cascade may already have removed a delegated grant
+            if remove_permission(&env, &item.grantee, &item.resource_id, |_| true) {
+                revoke_delegations(&env, &item.grantee, &item.resource_id);
+            }
+
+            env.events().publish(
+                (symbol_short!("revoke"), item.grantee, item.resource_id),
+                symbol_short!("batch"),
+            );
+        }
+        outcomes
+    }
+
+    /// Revoke every grant an entity has made, e.g. when a staff member leaves
+    ///
+    /// # Arguments
+    /// * `caller` - The grantor itself or the admin
+    /// * `grantor` - The entity whose grants are revoked
+    /// * `limit` - Most grants to revoke in this call, at most `MAX_BATCH_SIZE`
+    ///
+    /// # Returns
+    /// The number of grants revoked, not counting grants re-shared from them.
+    /// Call again until it returns 0 to clear grantors with many grants.
+    pub fn revoke_all_granted_by(env: Env, caller: Address, grantor: Address, limit: u32) -> u32 {
+        caller.require_auth();
+
+        if caller != grantor && !is_admin(&env, &caller) {
+            panic!("Not authorized to revoke these grants");
+        }
+
+        let limit = limit.min(MAX_BATCH_SIZE);
+        let mut revoked = 0;
+        for purpose in [
+            Purpose::Treatment,
+            Purpose::Payment,
+            Purpose::Operations,
+            Purpose::Research,
+            Purpose::PublicHealth,
+        ] {
+            let index = GrantIndex::GrantorPurpose(grantor.clone(), purpose);
+            revoked += revoke_last(&env, &index, limit - revoked);
+        }
+
+        env.events().publish(
+            (symbol_short!("rvk_all"), grantor),
+            (caller, revoked),
+        );
+        revoked
+    }
+
+    /// Check if an entity may perform an action on a specific resource
+    ///
+    /// # Arguments
+    /// * `entity` - The address to check
+    /// * `resource_id` - The identifier of the resource
+    /// * `action` - The action the entity wants to perform
+    /// * `purpose` - The purpose of use the entity declares for this access
+    ///
+    /// # Returns
+    /// `true` if the entity has valid (non-expired) access at that level, `false` otherwise.
+    /// The most specific matching grant or exclusion wins; on a tie the exclusion wins.
+    pub fn check_access(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: AccessAction,
+        purpose: Purpose,
+    ) -> bool {
+        // Deactivated entities hold no access at all
+        if !is_active(&env, &entity) {
+            return false;
+        }
+
+        let current_time = env.ledger().timestamp();
+
+        // Live break-glass access allows reading for treatment regardless of exclusions
+        if action == AccessAction::Read && purpose == Purpose::Treatment {
+            let emergency_id: Option<u64> = env.storage().persistent().get(
+                &DataKey::EmergencyGrant(entity.clone(), resource_id.clone()),
+            );
+            if let Some(id) = emergency_id {
+                let emergency: EmergencyAccess = env
+                    .storage()
+                    .persistent()
+                    .get(&DataKey::EmergencyAccess(id))
+                    .expect("Emergency access not found");
+                if emergency.expires_at > current_time {
+                    return true;
+                }
+            }
+        }
+
+        let mut allowed: Option<u32> = None;
+
+        // Only grants on the resource itself or a wildcard above it can apply
+        for pattern in covering_patterns(&env, &resource_id).iter() {
+            if let Some(permission) = find_permission(&env, &entity, &pattern) {
+                // Check if permission is below the requested level, or expired, out of schedule
+                // or deactivated anywhere along the chain it was shared down
+                let live = grant_chain_ok(&env, &permission, &resource_id, current_time);
+                if live && permission.level >= action && permission.purpose == purpose {
+                    allowed = allowed.max(match_specificity(&permission.resource_id, &resource_id));
+                }
+            }
+        }
+
+        // Roles the entity holds count as grants too
+        let assignments: Vec<RoleAssignment> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::EntityRoles(entity.clone()))
+            .unwrap_or(Vec::new(&env));
+
+        for assignment in assignments.iter() {
+            if !is_active(&env, &assignment.scope) {
+                continue;
+            }
+            let role: Option<RoleData> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Role(assignment.scope, assignment.role));
+            if let Some(role) = role {
+                for rule in role
+                    .rules
+                    .iter()
+                    .filter(|rule| rule.actions.contains(action) && rule.purposes.contains(purpose))
+                {
+                    allowed = allowed.max(match_specificity(&rule.resource_pattern, &resource_id));
+                }
+            }
+        }
+
+        match (allowed, excluded(&env, &entity, &resource_id)) {
+            (Some(allowed), Some(excluded)) => allowed > excluded,
+            (Some(_), None) => true,
+            _ => false,
+        }
+    }
+
+    /// Check access like `check_access`, additionally requiring a verified entity type
+    ///
+    /// # Arguments
+    /// * `entity` - The address to check
+    /// * `resource_id` - The identifier of the resource
+    /// * `action` - The action the entity wants to perform
+    /// * `purpose` - The purpose of use the entity declares for this access
+    /// * `entity_type` - The type the entity must be registered and verified as
+    pub fn check_verified_access(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: AccessAction,
+        purpose: Purpose,
+        entity_type: EntityType,
+    ) -> bool {
+        if !verified_type(&env, &entity, &entity_type) {
+            return false;
+        }
+        Self::check_access(env, entity, resource_id, action, purpose)
+    }
+
+    /// Check access like `check_access` and record the attempt in the resource's access log
+    ///
+    /// # Arguments
+    /// * `accessor` - The entity accessing the resource
+    /// * `resource_id` - The identifier of the resource, which must be registered
+    /// * `action` - The action the entity is performing
+    /// * `purpose` - The purpose of use the entity declares for this access
+    ///
+    /// # Returns
+    /// Whether access is allowed; denied attempts are logged too
+    pub fn access_resource(
+        env: Env,
+        accessor: Address,
+        resource_id: String,
+        action: AccessAction,
+        purpose: Purpose,
+    ) -> bool {
+        accessor.require_auth();
+
+        let record = find_resource(&env, &resource_id).expect("Resource not registered");
+        let allowed = Self::check_access(
+            env.clone(),
+            accessor.clone(),
+            resource_id.clone(),
+            action,
+            purpose,
+        );
+
+        // Logs belong to the registered record, so a subtree's log covers all of it
+        let len_key = DataKey::AccessLogLen(record.resource_id.clone());
+        let position: u32 = env.storage().persistent().get(&len_key).unwrap_or(0);
+        let entry = AccessLogEntry {
+            accessor: accessor.clone(),
+            resource_id: resource_id.clone(),
+            action,
+            purpose,
+            allowed,
+            accessed_at: env.ledger().timestamp(),
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::AccessLog(record.resource_id, position), &entry);
+        env.storage().persistent().set(&len_key, &(position + 1));
+
+        env.events().publish(
+            (symbol_short!("access"), accessor, resource_id),
+            (purpose, allowed),
+        );
+        allowed
+    }
+
+    /// Get a page of a resource's access log, oldest first
+    ///
+    /// # Arguments
+    /// * `viewer` - An owner or co-owner of the resource, or the admin
+    /// * `resource_id` - The registered resource or subtree whose log to read
+    /// * `start` - Position of the first entry to return
+    /// * `limit` - How many entries to return, at most `MAX_PAGE_SIZE`
+    pub fn get_access_log(
+        env: Env,
+        viewer: Address,
+        resource_id: String,
+        start: u32,
+        limit: u32,
+    ) -> Vec<AccessLogEntry> {
+        viewer.require_auth();
+
+        let record: ResourceRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Resource(resource_id.clone()))
+            .expect("Resource not registered");
+        if !is_owner(&record, &viewer) && !is_admin(&env, &viewer) {
+            panic!("Not authorized to view this access log");
+        }
+
+        let len: u32 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::AccessLogLen(resource_id.clone()))
+            .unwrap_or(0);
+        let end = len.min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+        let mut entries: Vec<AccessLogEntry> = Vec::new(&env);
+        for position in start..end {
+            let entry: AccessLogEntry = env
+                .storage()
+                .persistent()
+                .get(&DataKey::AccessLog(resource_id.clone(), position))
+                .expect("Access log entry not found");
+            entries.push_back(entry);
+        }
+        entries
+    }
+
+    /// Get how many entries a resource's access log holds
+    ///
+    /// # Arguments
+    /// * `resource_id` - The registered resource or subtree
+    pub fn get_access_log_len(env: Env, resource_id: String) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AccessLogLen(resource_id))
+            .unwrap_or(0)
+    }
+
+    /// Break-glass: take short-lived read access to a resource without prior consent
+    ///
+    /// # Arguments
+    /// * `doctor` - The treating doctor (must be an active doctor verified by an attester)
+    /// * `resource_id` - The identifier of a registered resource
+    /// * `justification` - Why the records are needed now
+    ///
+    /// # Returns
+    /// The id of the emergency-access record, queued for admin review
+    pub fn break_glass(
+        env: Env,
+        doctor: Address,
+        resource_id: String,
+        justification: String,
+    ) -> u64 {
+        doctor.require_auth();
+
+        // A self-declared type is not enough to override consent
+        if !is_active(&env, &doctor) || !verified_type(&env, &doctor, &EntityType::Doctor) {
+            panic!("Only active, verified doctors can break glass");
+        }
+        if justification.is_empty() {
+            panic!("Justification required");
+        }
+        validate_resource_id(&resource_id);
+        if is_wildcard(&resource_id) {
+            panic!("Break-glass access must name a single resource");
+        }
+        let owner = find_resource(&env, &resource_id)
+            .expect("Resource not registered")
+            .owner;
+
+        let id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::EmergencyCounter)
+            .unwrap_or(0)
+            + 1;
+        env.storage()
+            .persistent()
+            .set(&DataKey::EmergencyCounter, &id);
+
+        let now = env.ledger().timestamp();
+        let emergency = EmergencyAccess {
+            id,
+            doctor: doctor.clone(),
+            owner: owner.clone(),
+            resource_id: resource_id.clone(),
+            justification,
+            accessed_at: now,
+            expires_at: now + BREAK_GLASS_PERIOD,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::EmergencyAccess(id), &emergency);
+
+        // Breaking glass again on the same resource replaces the earlier window
+        env.storage().persistent().set(
+            &DataKey::EmergencyGrant(doctor.clone(), resource_id.clone()),
+            &id,
+        );
+
+        // Owner-topic event so the patient hears about it straight away
+        env.events().publish(
+            (symbol_short!("brk_glass"), owner, resource_id),
+            (id, doctor),
+        );
+
+        id
+    }
+
+    /// Record the admin's post-hoc review of a break-glass access
+    ///
+    /// # Arguments
+    /// * `admin` - The admin address
+    /// * `id` - The emergency-access record id
+    /// * `outcome` - Approved, or Flagged to cut off any remaining access
+    /// * `notes` - Reviewer notes
+    pub fn review_emergency_access(
+        env: Env,
+        admin: Address,
+        id: u64,
+        outcome: ReviewOutcome,
+        notes: String,
+    ) {
+        admin.require_auth();
+
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("Contract not initialized");
+        if admin != stored_admin {
+            panic!("Only admin can review emergency access");
+        }
+
+        let emergency: EmergencyAccess = env
+            .storage()
+            .persistent()
+            .get(&DataKey::EmergencyAccess(id))
+            .expect("Emergency access not found");
+
+        let review_key = DataKey::EmergencyReview(id);
+        if env.storage().persistent().has(&review_key) {
+            panic!("Emergency access already reviewed");
+        }
+        let review = EmergencyReview {
+            outcome,
+            reviewer: admin,
+            notes,
+            reviewed_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&review_key, &review);
+
+        if outcome == ReviewOutcome::Flagged {
+            let grant_key = DataKey::EmergencyGrant(emergency.doctor, emergency.resource_id);
+            let live_id: Option<u64> = env.storage().persistent().get(&grant_key);
+            if live_id == Some(id) {
+                env.storage().persistent().remove(&grant_key);
+            }
+        }
+
+        env.events().publish(
+            (symbol_short!("brk_rev"), emergency.owner, id),
+            outcome,
+        );
+    }
+
+    /// Get a break-glass record
+    ///
+    /// # Arguments
+    /// * `id` - The emergency-access record id
+    pub fn get_emergency_access(env: Env, id: u64) -> EmergencyAccess {
+        env.storage()
+            .persistent()
+            .get(&DataKey::EmergencyAccess(id))
+            .expect("Emergency access not found")
+    }
+
+    /// Get the admin review of a break-glass record, if it has been reviewed
+    ///
+    /// # Arguments
+    /// * `id` - The emergency-access record id
+    pub fn get_emergency_review(env: Env, id: u64) -> Option<EmergencyReview> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::EmergencyReview(id))
+    }
+
+    /// Get the break-glass records still awaiting admin review
+    ///
+    /// # Arguments
+    /// * `start` - The first record id to scan
+    /// * `limit` - How many record ids to scan, at most `MAX_PAGE_SIZE`
+    ///
+    /// # Returns
+    /// The unreviewed ids among those scanned, in id order; pages may come back short
+    pub fn get_pending_reviews(env: Env, start: u64, limit: u32) -> Vec<u64> {
+        let last: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::EmergencyCounter)
+            .unwrap_or(0);
+
+        let mut pending: Vec<u64> = Vec::new(&env);
+        let end = start
+            .saturating_add(limit.min(MAX_PAGE_SIZE) as u64)
+            .min(last.saturating_add(1));
+        for id in start.max(1)..end {
+            if !env
+                .storage()
+                .persistent()
+                .has(&DataKey::EmergencyReview(id))
+            {
+                pending.push_back(id);
+            }
+        }
+        pending
+    }
+
+    /// Get the resources carved out of an entity's wildcard grants
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    pub fn get_entity_exclusions(env: Env, wallet: Address) -> Vec<AccessExclusion> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Exclusions(wallet))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Define or replace a named role within the caller's scope
+    ///
+    /// # Arguments
+    /// * `scope` - The entity defining the role, typically a hospital
+    /// * `name` - The role name (e.g. "attending", "billing_clerk")
+    /// * `rules` - The actions the role allows on each resource pattern
+    pub fn define_role(env: Env, scope: Address, name: String, rules: Vec<RoleRule>) {
+        scope.require_auth();
+
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Entity(scope.clone()))
+        {
+            panic!("Scope not registered");
+        }
+        if !is_active(&env, &scope) {
+            panic!("Scope is deactivated");
+        }
+
+        if rules.is_empty() {
+            panic!("Role must have at least one rule");
+        }
+        if rules.len() > MAX_ROLE_RULES {
+            panic!("Too many role rules");
+        }
+        for rule in rules.iter() {
+            if rule.actions.is_empty() || rule.purposes.is_empty() {
+                panic!("Role rule must allow at least one action and purpose");
+            }
+            validate_resource_id(&rule.resource_pattern);
+            if !can_manage(&env, &scope, &rule.resource_pattern) {
+                panic!("Not authorized to grant access to this resource");
+            }
+        }
+
+        let role = RoleData {
+            name: name.clone(),
+            rules,
+            updated_at: env.ledger().timestamp(),
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Role(scope.clone(), name.clone()), &role);
+
+        env.events().publish(
+            (symbol_short!("role_def"), scope, name),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Remove a role definition; existing assignments stop granting access
+    ///
+    /// # Arguments
+    /// * `scope` - The entity that defined the role
+    /// * `name` - The role name
+    pub fn remove_role(env: Env, scope: Address, name: String) {
+        scope.require_auth();
+
+        let key = DataKey::Role(scope.clone(), name.clone());
+        if !env.storage().persistent().has(&key) {
+            panic!("Role not found");
+        }
+        env.storage().persistent().remove(&key);
+
+        env.events().publish(
+            (symbol_short!("role_rm"), scope, name),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Assign one of the scope's roles to an entity
+    ///
+    /// # Arguments
+    /// * `scope` - The entity that defined the role
+    /// * `entity` - The entity receiving the role
+    /// * `name` - The role name
+    pub fn assign_role(env: Env, scope: Address, entity: Address, name: String) {
+        scope.require_auth();
+
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Role(scope.clone(), name.clone()))
+        {
+            panic!("Role not found");
+        }
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Entity(entity.clone()))
+        {
+            panic!("Entity not registered");
+        }
+        if !is_active(&env, &entity) {
+            panic!("Entity is deactivated");
+        }
+
+        let roles_key = DataKey::EntityRoles(entity.clone());
+        let mut assignments: Vec<RoleAssignment> = env
+            .storage()
+            .persistent()
+            .get(&roles_key)
+            .unwrap_or(Vec::new(&env));
+
+        for assignment in assignments.iter() {
+            if assignment.scope == scope && assignment.role == name {
+                panic!("Role already assigned");
+            }
+        }
+        if assignments.len() >= MAX_ENTITY_ROLES {
+            panic!("Too many roles assigned");
+        }
+
+        assignments.push_back(RoleAssignment {
+            scope: scope.clone(),
+            role: name.clone(),
+            assigned_at: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&roles_key, &assignments);
+
+        env.events().publish(
+            (symbol_short!("role_asgn"), entity, name),
+            scope,
+        );
+    }
+
+    /// Take a role away from an entity
+    ///
+    /// # Arguments
+    /// * `scope` - The entity that assigned the role
+    /// * `entity` - The entity losing the role
+    /// * `name` - The role name
+    pub fn unassign_role(env: Env, scope: Address, entity: Address, name: String) {
+        scope.require_auth();
+
+        let roles_key = DataKey::EntityRoles(entity.clone());
+        let assignments: Vec<RoleAssignment> = env
+            .storage()
+            .persistent()
+            .get(&roles_key)
+            .unwrap_or(Vec::new(&env));
+
+        let mut remaining: Vec<RoleAssignment> = Vec::new(&env);
+        let mut found = false;
+        for assignment in assignments.iter() {
+            if assignment.scope == scope && assignment.role == name {
+                found = true;
+            } else {
+                remaining.push_back(assignment);
+            }
+        }
+
+        if !found {
+            panic!("Role not assigned");
+        }
+        env.storage().persistent().set(&roles_key, &remaining);
+
+        env.events().publish(
+            (symbol_short!("role_unas"), entity, name),
+            scope,
+        );
+    }
+
+    /// Get a role definition
+    ///
+    /// # Arguments
+    /// * `scope` - The entity that defined the role
+    /// * `name` - The role name
+    pub fn get_role(env: Env, scope: Address, name: String) -> RoleData {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Role(scope, name))
+            .expect("Role not found")
+    }
+
+    /// Get all roles assigned to an entity
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    pub fn get_entity_roles(env: Env, wallet: Address) -> Vec<RoleAssignment> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::EntityRoles(wallet))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Get a page of the entities granted access to a specific resource
+    ///
+    /// # Arguments
+    /// * `resource_id` - The identifier of the resource, matched exactly
+    /// * `start` - Position of the first entity to return
+    /// * `limit` - How many entities to return, at most `MAX_PAGE_SIZE`
+    ///
+    /// # Returns
+    /// A vector of addresses that have access to the resource
+    pub fn get_authorized_parties(
+        env: Env,
+        resource_id: String,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Address> {
+        let mut parties: Vec<Address> = Vec::new(&env);
+        for grant in index_page(&env, &GrantIndex::Resource(resource_id), start, limit).iter() {
+            parties.push_back(grant.grantee);
+        }
+        parties
+    }
+
+    /// Get how many entities are granted access to a specific resource
+    ///
+    /// # Arguments
+    /// * `resource_id` - The identifier of the resource, matched exactly
+    pub fn get_authorized_party_count(env: Env, resource_id: String) -> u32 {
+        index_len(&env, &GrantIndex::Resource(resource_id))
+    }
+
+    /// Get entity details by wallet address
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    ///
+    /// # Returns
+    /// The EntityData for the given wallet address
+    pub fn get_entity(env: Env, wallet: Address) -> EntityData {
+        load_entity(&env, &wallet).expect("Entity not found")
+    }
+
+    /// Get a page of the access permissions granted to an entity
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    /// * `start` - Position of the first permission to return
+    /// * `limit` - How many permissions to return, at most `MAX_PAGE_SIZE`
+    ///
+    /// # Returns
+    /// A vector of access permissions granted to the entity
+    pub fn get_entity_permissions(
+        env: Env,
+        wallet: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<AccessPermission> {
+        let mut permissions: Vec<AccessPermission> = Vec::new(&env);
+        for grant in index_page(&env, &GrantIndex::Holder(wallet), start, limit).iter() {
+            if let Some(permission) = find_permission(&env, &grant.grantee, &grant.resource_id) {
+                permissions.push_back(permission);
+            }
+        }
+        permissions
+    }
+
+    /// Get how many access permissions are granted to an entity
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    pub fn get_permission_count(env: Env, wallet: Address) -> u32 {
+        index_len(&env, &GrantIndex::Holder(wallet))
+    }
+
+    /// Move entities' grants out of the legacy per-entity and per-resource vectors, and
+    /// rewrite their entity records in the current layout
+    ///
+    /// Legacy grants become Read grants for treatment with no re-sharing or schedule.
+    /// They are not honoured until their holder is migrated, so this should be run for
+    /// every registered entity after upgrading.
+    ///
+    /// # Arguments
+    /// * `admin` - The admin address
+    /// * `wallets` - The entities to migrate, at most `MAX_BATCH_SIZE` per call
+    ///
+    /// At most `MAX_BATCH_SIZE` legacy grants are moved per call; any left over stay in
+    /// the legacy vector for the next call with the same wallets.
+    ///
     /// # Returns
-    /// A vector of all access permissions granted to the entity
-    pub fn get_entity_permissions(env: Env, wallet: Address) -> Vec<AccessPermission> {
-        let access_key = DataKey::AccessList(wallet);
+    /// The number of permissions moved
+    pub fn migrate_access_lists(env: Env, admin: Address, wallets: Vec<Address>) -> u32 {
+        admin.require_auth();
+
+        if !is_admin(&env, &admin) {
+            panic!("Only admin can migrate access lists");
+        }
+        if wallets.len() > MAX_BATCH_SIZE {
+            panic!("Batch too large");
+        }
+
+        let mut moved = 0;
+        let mut budget = MAX_BATCH_SIZE;
+        for wallet in wallets.iter() {
+            if budget == 0 {
+                break;
+            }
+            if let Some(entity) = load_entity(&env, &wallet) {
+                env.storage()
+                    .persistent()
+                    .set(&DataKey::Entity(wallet.clone()), &entity);
+            }
+
+            let access_key = DataKey::AccessList(wallet.clone());
+            let legacy: Vec<LegacyAccessPermission> =
+                match env.storage().persistent().get(&access_key) {
+                    Some(legacy) => legacy,
+                    None => continue,
+                };
+
+            let mut left: Vec<LegacyAccessPermission> = Vec::new(&env);
+            for legacy_permission in legacy.iter() {
+                if budget == 0 {
+                    left.push_back(legacy_permission);
+                    continue;
+                }
+                budget -= 1;
+
+                let resource_id = legacy_permission.resource_id.clone();
+                if find_permission(&env, &wallet, &resource_id).is_none() {
+                    let permission = AccessPermission {
+                        resource_id: resource_id.clone(),
+                        level: AccessAction::Read,
+                        purpose: Purpose::Treatment,
+                        granted_by: legacy_permission.granted_by,
+                        granted_at: legacy_permission.granted_at,
+                        expires_at: legacy_permission.expires_at,
+                        delegation_depth: 0,
+                        parent_resource: None,
+                        not_before: 0,
+                        windows: Vec::new(&env),
+                    };
+                    insert_permission(&env, &wallet, permission);
+                    moved += 1;
+                }
+
+                // Shrink the legacy authorized-parties vector as its grantees move over
+                let resource_key = DataKey::ResourceAccess(resource_id);
+                let authorized: Option<Vec<Address>> =
+                    env.storage().persistent().get(&resource_key);
+                if let Some(authorized) = authorized {
+                    let mut remaining: Vec<Address> = Vec::new(&env);
+                    for addr in authorized.iter() {
+                        if addr != wallet {
+                            remaining.push_back(addr);
+                        }
+                    }
+                    if remaining.is_empty() {
+                        env.storage().persistent().remove(&resource_key);
+                    } else {
+                        env.storage().persistent().set(&resource_key, &remaining);
+                    }
+                }
+            }
+            if left.is_empty() {
+                env.storage().persistent().remove(&access_key);
+            } else {
+                env.storage().persistent().set(&access_key, &left);
+            }
+        }
+
+        env.events().publish(
+            (symbol_short!("migrate"), admin),
+            moved,
+        );
+        moved
+    }
+
+    /// Update entity metadata
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    /// * `metadata` - Updated metadata information
+    pub fn update_entity(env: Env, wallet: Address, metadata: String) {
+        wallet.require_auth();
+
+        let key = DataKey::Entity(wallet.clone());
+        let mut entity = load_entity(&env, &wallet).expect("Entity not found");
+
+        entity.metadata = metadata;
+        env.storage().persistent().set(&key, &entity);
+
+        env.events().publish(
+            (symbol_short!("upd_ent"), wallet),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Change an entity's type (admin only); this is the only way to assign Admin
+    ///
+    /// # Arguments
+    /// * `admin` - The admin address
+    /// * `wallet` - The wallet address of the entity
+    /// * `entity_type` - The new type, which clears any attestation of the old one
+    pub fn set_entity_type(env: Env, admin: Address, wallet: Address, entity_type: EntityType) {
+        admin.require_auth();
+
+        if !is_admin(&env, &admin) {
+            panic!("Only admin can set entity types");
+        }
+
+        let key = DataKey::Entity(wallet.clone());
+        let mut entity = load_entity(&env, &wallet).expect("Entity not found");
+
+        entity.entity_type = entity_type.clone();
+        env.storage().persistent().set(&key, &entity);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Attestation(wallet.clone()));
+        let assigned_key = DataKey::AdminAssigned(wallet.clone());
+        if entity_type == EntityType::Admin {
+            env.storage().persistent().set(&assigned_key, &true);
+        } else {
+            env.storage().persistent().remove(&assigned_key);
+        }
+
+        env.events().publish(
+            (symbol_short!("set_type"), wallet),
+            entity_type,
+        );
+    }
+
+    /// Appoint a trusted attester, e.g. a licensing board (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - The admin address
+    /// * `attester` - The address that may verify entity types
+    pub fn add_attester(env: Env, admin: Address, attester: Address) {
+        admin.require_auth();
+
+        if !is_admin(&env, &admin) {
+            panic!("Only admin can manage attesters");
+        }
+
+        let mut attesters = Self::get_attesters(env.clone());
+        if attesters.contains(&attester) {
+            panic!("Already an attester");
+        }
+        attesters.push_back(attester.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::Attesters, &attesters);
+
+        env.events().publish(
+            (symbol_short!("add_att"), attester),
+            admin,
+        );
+    }
+
+    /// Remove a trusted attester (admin only); the verifications it made stop counting
+    ///
+    /// # Arguments
+    /// * `admin` - The admin address
+    /// * `attester` - The attester to remove
+    pub fn remove_attester(env: Env, admin: Address, attester: Address) {
+        admin.require_auth();
+
+        if !is_admin(&env, &admin) {
+            panic!("Only admin can manage attesters");
+        }
+
+        let mut attesters = Self::get_attesters(env.clone());
+        let index = attesters
+            .first_index_of(&attester)
+            .expect("Not an attester");
+        attesters.remove(index);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Attesters, &attesters);
+
+        env.events().publish(
+            (symbol_short!("rm_att"), attester),
+            admin,
+        );
+    }
+
+    /// Get the trusted attesters
+    pub fn get_attesters(env: Env) -> Vec<Address> {
         env.storage()
             .persistent()
-            .get(&access_key)
+            .get(&DataKey::Attesters)
             .unwrap_or(Vec::new(&env))
     }
 
-    /// Update entity metadata
+    /// Verify an entity's declared type
     ///
     /// # Arguments
+    /// * `attester` - A trusted attester
     /// * `wallet` - The wallet address of the entity
-    /// * `metadata` - Updated metadata information
-    pub fn update_entity(env: Env, wallet: Address, metadata: String) {
-        wallet.require_auth();
+    /// * `entity_type` - The type being verified, which must match the declared type
+    /// * `evidence_hash` - Hash of the off-chain evidence, e.g. a license record
+    pub fn attest_entity(
+        env: Env,
+        attester: Address,
+        wallet: Address,
+        entity_type: EntityType,
+        evidence_hash: BytesN<32>,
+    ) {
+        attester.require_auth();
 
-        let key = DataKey::Entity(wallet.clone());
-        let mut entity: EntityData = env
+        if !Self::get_attesters(env.clone()).contains(&attester) {
+            panic!("Not a trusted attester");
+        }
+        if entity_type == EntityType::Admin {
+            panic!("Only admin can assign the Admin type");
+        }
+
+        let entity = Self::get_entity(env.clone(), wallet.clone());
+        if entity.entity_type != entity_type {
+            panic!("Entity type does not match registration");
+        }
+
+        let attestation = Attestation {
+            entity_type,
+            attester: attester.clone(),
+            evidence_hash,
+            attested_at: env.ledger().timestamp(),
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Attestation(wallet.clone()), &attestation);
+
+        env.events().publish(
+            (symbol_short!("attest"), wallet),
+            attester,
+        );
+    }
+
+    /// Withdraw an entity's verification
+    ///
+    /// # Arguments
+    /// * `caller` - The attester that made the attestation, or the admin
+    /// * `wallet` - The wallet address of the entity
+    pub fn revoke_attestation(env: Env, caller: Address, wallet: Address) {
+        caller.require_auth();
+
+        let key = DataKey::Attestation(wallet.clone());
+        let attestation: Attestation = env
             .storage()
             .persistent()
             .get(&key)
-            .expect("Entity not found");
-
-        entity.metadata = metadata;
-        env.storage().persistent().set(&key, &entity);
+            .expect("Attestation not found");
+        if attestation.attester != caller && !is_admin(&env, &caller) {
+            panic!("Not authorized to revoke this attestation");
+        }
+        env.storage().persistent().remove(&key);
 
         env.events().publish(
-            (symbol_short!("upd_ent"), wallet),
-            symbol_short!("success"),
+            (symbol_short!("rvk_att"), wallet),
+            caller,
         );
     }
 
-    /// Deactivate an entity (admin only)
+    /// Get an entity's attestation, if it has one
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    pub fn get_attestation(env: Env, wallet: Address) -> Option<Attestation> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Attestation(wallet))
+    }
+
+    /// Whether an entity's type is verified: attested by a current attester, or Admin as
+    /// assigned by the admin
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    pub fn is_verified(env: Env, wallet: Address) -> bool {
+        match load_entity(&env, &wallet) {
+            Some(entity) => verified_type(&env, &wallet, &entity.entity_type),
+            None => false,
+        }
+    }
+
+    /// Deactivate an entity (admin only); it loses all access and grants it made stop counting
     ///
     /// # Arguments
     /// * `admin` - The admin address
     /// * `wallet` - The wallet address of the entity to deactivate
-    pub fn deactivate_entity(env: Env, admin: Address, wallet: Address) {
+    /// * `reason` - Why the entity was deactivated, kept for audit
+    pub fn deactivate_entity(env: Env, admin: Address, wallet: Address, reason: String) {
         admin.require_auth();
 
         let stored_admin: Address = env
@@ -387,18 +2233,652 @@ impl AccessControl {
         }
 
         let key = DataKey::Entity(wallet.clone());
-        let mut entity: EntityData = env
-            .storage()
-            .persistent()
-            .get(&key)
-            .expect("Entity not found");
+        let mut entity = load_entity(&env, &wallet).expect("Entity not found");
 
         entity.active = false;
+        entity.deactivated_at = env.ledger().timestamp();
+        entity.deactivation_reason = reason.clone();
         env.storage().persistent().set(&key, &entity);
 
         env.events().publish(
             (symbol_short!("deact"), wallet),
+            reason,
+        );
+    }
+
+    /// Reactivate a deactivated entity (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - The admin address
+    /// * `wallet` - The wallet address of the entity to reactivate
+    pub fn reactivate_entity(env: Env, admin: Address, wallet: Address) {
+        admin.require_auth();
+
+        let stored_admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .expect("Contract not initialized");
+
+        if admin != stored_admin {
+            panic!("Only admin can reactivate entities");
+        }
+
+        let key = DataKey::Entity(wallet.clone());
+        let mut entity = load_entity(&env, &wallet).expect("Entity not found");
+
+        if entity.active {
+            panic!("Entity is already active");
+        }
+
+        // The last deactivation reason and time stay on record
+        entity.active = true;
+        env.storage().persistent().set(&key, &entity);
+
+        env.events().publish(
+            (symbol_short!("react"), wallet),
             symbol_short!("success"),
         );
     }
 }
+
+/// Whether `wallet` is registered as `entity_type` and that type is verified
+fn verified_type(env: &Env, wallet: &Address, entity_type: &EntityType) -> bool {
+    match load_entity(env, wallet) {
+        Some(entity) if entity.entity_type == *entity_type => {}
+        _ => return false,
+    }
+    // Only the admin can assign Admin, so it needs no attester; entities that registered
+    // themselves as Admin before that rule are not trusted
+    if *entity_type == EntityType::Admin {
+        return env
+            .storage()
+            .persistent()
+            .has(&DataKey::AdminAssigned(wallet.clone()));
+    }
+
+    let attestation: Option<Attestation> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Attestation(wallet.clone()));
+    match attestation {
+        Some(attestation) => {
+            let attesters: Vec<Address> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Attesters)
+                .unwrap_or(Vec::new(env));
+            attestation.entity_type == *entity_type && attesters.contains(&attestation.attester)
+        }
+        None => false,
+    }
+}
+
+/// Whether `wallet` is a registered entity that has not been deactivated
+fn is_active(env: &Env, wallet: &Address) -> bool {
+    load_entity(env, wallet).is_some_and(|entity| entity.active)
+}
+
+/// `wallet`'s entity record, upgrading one stored in the legacy layout
+fn load_entity(env: &Env, wallet: &Address) -> Option<EntityData> {
+    let key = DataKey::Entity(wallet.clone());
+    // Decoding a struct with the wrong fields traps, so look at the field names first
+    let fields: Map<Symbol, Val> = env.storage().persistent().get(&key)?;
+    if fields.contains_key(Symbol::new(env, "deactivated_at")) {
+        return env.storage().persistent().get(&key);
+    }
+
+    let legacy: LegacyEntityData = env.storage().persistent().get(&key)?;
+    Some(EntityData {
+        entity_type: legacy.entity_type,
+        name: legacy.name,
+        metadata: legacy.metadata,
+        active: legacy.active,
+        deactivated_at: 0,
+        deactivation_reason: String::from_str(env, ""),
+    })
+}
+
+/// The record of `resource_id` itself, else of the nearest registered subtree above it
+fn find_resource(env: &Env, resource_id: &String) -> Option<ResourceRecord> {
+    covering_patterns(env, resource_id)
+        .iter()
+        .find_map(|pattern| env.storage().persistent().get(&DataKey::Resource(pattern)))
+}
+
+/// Whether a non-admin `owner` may claim `resource_id`: it must not be "*" or a top-level
+/// subtree, and anything under "patient/" must be under "patient/<owner's address>/"
+fn in_own_namespace(owner: &Address, resource_id: &String) -> bool {
+    let len = resource_id.len() as usize;
+    let mut buf = [0u8; MAX_RESOURCE_ID_LEN];
+    resource_id.copy_into_slice(&mut buf[..len]);
+    let path = &buf[..len];
+
+    // "*" and "ward/*" would claim every resource without an owner of its own
+    if path[len - 1] == b'*' && path.iter().filter(|b| **b == b'/').count() <= 1 {
+        return false;
+    }
+
+    let mut segments = path.split(|b| *b == b'/');
+    if segments.next() != Some(b"patient".as_slice()) {
+        return true;
+    }
+    let address = owner.to_string();
+    let address_len = address.len() as usize;
+    let mut address_buf = [0u8; MAX_RESOURCE_ID_LEN];
+    if address_len > MAX_RESOURCE_ID_LEN {
+        return false;
+    }
+    address.copy_into_slice(&mut address_buf[..address_len]);
+    segments.next() == Some(&address_buf[..address_len])
+}
+
+fn is_owner(record: &ResourceRecord, wallet: &Address) -> bool {
+    record.owner == *wallet || record.co_owners.contains(wallet)
+}
+
+fn is_admin(env: &Env, wallet: &Address) -> bool {
+    let admin: Option<Address> = env.storage().persistent().get(&DataKey::Admin);
+    admin.as_ref() == Some(wallet)
+}
+
+/// Whether `caller` may grant and revoke on `resource_id`: its owners or the admin
+fn can_manage(env: &Env, caller: &Address, resource_id: &String) -> bool {
+    if is_admin(env, caller) {
+        return true;
+    }
+    find_resource(env, resource_id).is_some_and(|record| is_owner(&record, caller))
+}
+
+/// Add `permission` to the grantee's permissions and the indexes over them
+fn store_permission(env: &Env, grantee: &Address, permission: AccessPermission) {
+    let resource_id = permission.resource_id.clone();
+
+    // Check if permission already exists for this resource
+    if find_permission(env, grantee, &resource_id).is_some() {
+        panic!("Access already granted for this resource");
+    }
+
+    insert_permission(env, grantee, permission);
+
+    // An explicit grant overrides an earlier exclusion of the same resource
+    remove_exclusions(env, grantee, &resource_id, false);
+}
+
+/// Write `permission` under its (grantee, resource) key and add it to every index
+fn insert_permission(env: &Env, grantee: &Address, permission: AccessPermission) {
+    let grant = GrantRef {
+        grantee: grantee.clone(),
+        resource_id: permission.resource_id.clone(),
+    };
+    index_add(env, &GrantIndex::Holder(grantee.clone()), &grant);
+    index_add(
+        env,
+        &GrantIndex::Resource(grant.resource_id.clone()),
+        &grant,
+    );
+    index_add(
+        env,
+        &GrantIndex::GrantorPurpose(permission.granted_by.clone(), permission.purpose),
+        &grant,
+    );
+    // Filed under the most specific registered resource; the admin may grant on unregistered ones
+    if let Some(record) = find_resource(env, &grant.resource_id) {
+        index_add(
+            env,
+            &GrantIndex::Subtree(record.resource_id, permission.purpose),
+            &grant,
+        );
+    }
+
+    env.storage().persistent().set(
+        &DataKey::Permission(grantee.clone(), grant.resource_id),
+        &permission,
+    );
+}
+
+/// Remove `grantee`'s permission on exactly `resource_id` if `matches` accepts it, along with
+/// its exclusions and index entries. Returns whether one was removed.
+fn remove_permission(
+    env: &Env,
+    grantee: &Address,
+    resource_id: &String,
+    matches: impl Fn(&AccessPermission) -> bool,
+) -> bool {
+    let removed = match find_permission(env, grantee, resource_id) {
+        Some(permission) if matches(&permission) => permission,
+        _ => return false,
+    };
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Permission(grantee.clone(), resource_id.clone()));
+
+    // Exclusions carved out of a revoked wildcard grant no longer mean anything, unless
+    // another of the grantee's wildcards still covers them
+    if is_wildcard(resource_id) {
+        remove_exclusions(env, grantee, resource_id, true);
+    }
+
+    let grant = GrantRef {
+        grantee: grantee.clone(),
+        resource_id: resource_id.clone(),
+    };
+    index_remove(env, &GrantIndex::Holder(grantee.clone()), &grant);
+    index_remove(env, &GrantIndex::Resource(resource_id.clone()), &grant);
+    index_remove(
+        env,
+        &GrantIndex::GrantorPurpose(removed.granted_by, removed.purpose),
+        &grant,
+    );
+    // Resources registered since the grant was made may cover it more specifically
+    for pattern in covering_patterns(env, resource_id).iter() {
+        index_remove(env, &GrantIndex::Subtree(pattern, removed.purpose), &grant);
+    }
+    true
+}
+
+/// `grantee`'s permission on exactly `resource_id`, if any
+fn find_permission(env: &Env, grantee: &Address, resource_id: &String) -> Option<AccessPermission> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Permission(grantee.clone(), resource_id.clone()))
+}
+
+fn index_len(env: &Env, index: &GrantIndex) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IndexLen(index.clone()))
+        .unwrap_or(0)
+}
+
+fn index_entry(env: &Env, index: &GrantIndex, position: u32) -> GrantRef {
+    env.storage()
+        .persistent()
+        .get(&DataKey::IndexEntry(index.clone(), position))
+        .expect("Index entry not found")
+}
+
+/// Append `grant` to `index`, remembering its position so it can be removed directly
+fn index_add(env: &Env, index: &GrantIndex, grant: &GrantRef) {
+    let len = index_len(env, index);
+    let storage = env.storage().persistent();
+    storage.set(&DataKey::IndexEntry(index.clone(), len), grant);
+    storage.set(
+        &DataKey::IndexSlot(
+            index.clone(),
+            grant.grantee.clone(),
+            grant.resource_id.clone(),
+        ),
+        &len,
+    );
+    storage.set(&DataKey::IndexLen(index.clone()), &(len + 1));
+}
+
+/// Remove `grant` from `index` by moving the last entry into its place
+fn index_remove(env: &Env, index: &GrantIndex, grant: &GrantRef) {
+    let slot_key = DataKey::IndexSlot(
+        index.clone(),
+        grant.grantee.clone(),
+        grant.resource_id.clone(),
+    );
+    let position: u32 = match env.storage().persistent().get(&slot_key) {
+        Some(position) => position,
+        None => return,
+    };
+    let last = index_len(env, index) - 1;
+    let storage = env.storage().persistent();
+    if position != last {
+        let moved = index_entry(env, index, last);
+        storage.set(&DataKey::IndexEntry(index.clone(), position), &moved);
+        storage.set(
+            &DataKey::IndexSlot(index.clone(), moved.grantee, moved.resource_id),
+            &position,
+        );
+    }
+    storage.remove(&DataKey::IndexEntry(index.clone(), last));
+    storage.remove(&slot_key);
+    if last == 0 {
+        storage.remove(&DataKey::IndexLen(index.clone()));
+    } else {
+        storage.set(&DataKey::IndexLen(index.clone()), &last);
+    }
+}
+
+/// Up to `limit` (capped at `MAX_PAGE_SIZE`) entries of `index` from position `start`
+fn index_page(env: &Env, index: &GrantIndex, start: u32, limit: u32) -> Vec<GrantRef> {
+    let end = index_len(env, index).min(start.saturating_add(limit.min(MAX_PAGE_SIZE)));
+    let mut page: Vec<GrantRef> = Vec::new(env);
+    for position in start..end {
+        page.push_back(index_entry(env, index, position));
+    }
+    page
+}
+
+/// The consents in `index` from position `start`, as `index_page` pages them
+fn consent_page(env: &Env, index: &GrantIndex, start: u32, limit: u32) -> Vec<Consent> {
+    let mut consents: Vec<Consent> = Vec::new(env);
+    for grant in index_page(env, index, start, limit).iter() {
+        if let Some(permission) = find_permission(env, &grant.grantee, &grant.resource_id) {
+            consents.push_back(Consent {
+                grantee: grant.grantee,
+                permission,
+            });
+        }
+    }
+    consents
+}
+
+/// Revoke up to `limit` (capped at `MAX_BATCH_SIZE`) grants from the end of `index`, along
+/// with everything re-shared from them. Returns how many grants in `index` were revoked.
+fn revoke_last(env: &Env, index: &GrantIndex, limit: u32) -> u32 {
+    // Take from the end of the index so removals don't move what is left to do
+    let limit = limit.min(MAX_BATCH_SIZE);
+    let len = index_len(env, index);
+    let grants = index_page(env, index, len.saturating_sub(limit), limit);
+    let mut revoked = 0;
+    for grant in grants.iter() {
+        // An earlier cascade in this call may already have removed it
+        if remove_permission(env, &grant.grantee, &grant.resource_id, |_| true) {
+            revoke_delegations(env, &grant.grantee, &grant.resource_id);
+            revoked += 1;
+        }
+    }
+    revoked
+}
+
+/// `resource_id` and every wildcard pattern above it, most specific first:
+/// "a/b/c" -> "a/b/*" -> "a/*" -> "*"
+fn covering_patterns(env: &Env, resource_id: &String) -> Vec<String> {
+    let mut patterns: Vec<String> = Vec::new(env);
+    patterns.push_back(resource_id.clone());
+
+    let len = resource_id.len() as usize;
+    if len > MAX_RESOURCE_ID_LEN {
+        return patterns;
+    }
+    let mut buf = [0u8; MAX_RESOURCE_ID_LEN + 1];
+    resource_id.copy_into_slice(&mut buf[..len]);
+
+    let mut end = len;
+    while let Some(slash) = buf[..end].iter().rposition(|b| *b == b'/') {
+        buf[slash + 1] = b'*';
+        let candidate = String::from_bytes(env, &buf[..slash + 2]);
+        // A pattern id such as "a/b/*" is its own first ancestor
+        if candidate != *resource_id {
+            patterns.push_back(candidate);
+        }
+        end = slash;
+    }
+    let root = String::from_str(env, "*");
+    if root != *resource_id {
+        patterns.push_back(root);
+    }
+    patterns
+}
+
+/// How specifically `entity`'s exclusions cover `resource_id`, or `None` if none do
+fn excluded(env: &Env, entity: &Address, resource_id: &String) -> Option<u32> {
+    let exclusions: Vec<AccessExclusion> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Exclusions(entity.clone()))
+        .unwrap_or(Vec::new(env));
+
+    let mut excluded: Option<u32> = None;
+    for exclusion in exclusions.iter() {
+        excluded = excluded.max(match_specificity(&exclusion.resource_id, resource_id));
+    }
+    excluded
+}
+
+/// Whether `permission` and every grant it was re-shared from are unexpired, usable at `time`
+/// and were made by an active entity or the admin, and no delegator along the way has had
+/// `resource_id` carved out of its own grant
+fn grant_chain_ok(
+    env: &Env,
+    permission: &AccessPermission,
+    resource_id: &String,
+    time: u64,
+) -> bool {
+    let mut current = permission.clone();
+    loop {
+        let live =
+            (current.expires_at == 0 || current.expires_at > time) && in_schedule(&current, time);
+        let grantor_ok = is_active(env, &current.granted_by) || is_admin(env, &current.granted_by);
+        if !live || !grantor_ok {
+            return false;
+        }
+
+        let parent_resource = match current.parent_resource.clone() {
+            Some(parent_resource) => parent_resource,
+            None => return true,
+        };
+        // Each hop has strictly more depth left than the one it was shared to
+        let parent = match find_permission(env, &current.granted_by, &parent_resource) {
+            Some(parent) if parent.delegation_depth > current.delegation_depth => parent,
+            _ => return false,
+        };
+        // The delegator can only pass on what it may still reach itself
+        let reach = match_specificity(&parent.resource_id, resource_id);
+        if reach.is_none() || excluded(env, &current.granted_by, resource_id) >= reach {
+            return false;
+        }
+        current = parent;
+    }
+}
+
+/// Revoke every grant re-shared, directly or transitively, from `holder`'s permission
+fn revoke_delegations(env: &Env, holder: &Address, resource_id: &String) {
+    let mut pending: Vec<GrantRef> = Vec::new(env);
+    pending.push_back(GrantRef {
+        grantee: holder.clone(),
+        resource_id: resource_id.clone(),
+    });
+
+    while let Some(parent) = pending.pop_front() {
+        let delegations_key =
+            DataKey::Delegations(parent.grantee.clone(), parent.resource_id.clone());
+        let delegations: Vec<GrantRef> = match env.storage().persistent().get(&delegations_key) {
+            Some(delegations) => delegations,
+            None => continue,
+        };
+        env.storage().persistent().remove(&delegations_key);
+
+        for child in delegations.iter() {
+            // The child may already have been revoked, or replaced by an unrelated grant
+            let removed = remove_permission(env, &child.grantee, &child.resource_id, |p| {
+                p.granted_by == parent.grantee
+                    && p.parent_resource == Some(parent.resource_id.clone())
+            });
+            if !removed {
+                continue;
+            }
+
+            env.events().publish(
+                (
+                    symbol_short!("revoke"),
+                    child.grantee.clone(),
+                    child.resource_id.clone(),
+                ),
+                symbol_short!("cascade"),
+            );
+            pending.push_back(child);
+        }
+    }
+}
+
+/// Whether `permission` has started and, if it has windows, `time` falls in one of them
+fn in_schedule(permission: &AccessPermission, time: u64) -> bool {
+    if time < permission.not_before {
+        return false;
+    }
+    if permission.windows.is_empty() {
+        return true;
+    }
+
+    let seconds = (time % SECONDS_PER_DAY as u64) as u32;
+    // 1970-01-01 was a Thursday; shift so that Monday is day 0
+    let day = ((time / SECONDS_PER_DAY as u64 + 3) % 7) as u32;
+    let previous_day = (day + 6) % 7;
+    permission.windows.iter().any(|window| {
+        if window.start < window.end {
+            window.days & (1 << day) != 0 && seconds >= window.start && seconds < window.end
+        } else {
+            // Overnight window: the evening part today, or the morning tail of yesterday's
+            (window.days & (1 << day) != 0 && seconds >= window.start)
+                || (window.days & (1 << previous_day) != 0 && seconds < window.end)
+        }
+    })
+}
+
+/// Panic unless `windows` are well-formed and access expiring at `expires_at` can ever start
+fn validate_schedule(expires_at: u64, not_before: u64, windows: &Vec<AccessWindow>) {
+    if expires_at != 0 && not_before >= expires_at {
+        panic!("Access would never become active");
+    }
+    for window in windows.iter() {
+        if window.days == 0
+            || window.days >= 1 << 7
+            || window.start >= SECONDS_PER_DAY
+            || window.end >= SECONDS_PER_DAY
+            || window.start == window.end
+        {
+            panic!("Invalid access window");
+        }
+    }
+}
+
+/// Whether a schedule starting at `not_before` with `windows` allows nothing `parent` doesn't
+fn schedule_within(
+    not_before: u64,
+    windows: &Vec<AccessWindow>,
+    parent: &AccessPermission,
+) -> bool {
+    if not_before < parent.not_before {
+        return false;
+    }
+    if parent.windows.is_empty() {
+        return true;
+    }
+    !windows.is_empty()
+        && windows.iter().all(|window| {
+            parent
+                .windows
+                .iter()
+                .any(|outer| window_within(&window, &outer))
+        })
+}
+
+/// Whether every moment `window` covers is also covered by `outer`
+fn window_within(window: &AccessWindow, outer: &AccessWindow) -> bool {
+    if window.days & !outer.days != 0 {
+        return false;
+    }
+    let overnight = window.end < window.start;
+    let outer_overnight = outer.end < outer.start;
+    if overnight == outer_overnight {
+        window.start >= outer.start && window.end <= outer.end
+    } else {
+        // A same-day window fits in the evening part of an overnight one
+        !overnight && window.start >= outer.start
+    }
+}
+
+/// Panic unless `resource_id` fits the buffer and only uses '*' as a final "/*" segment
+fn validate_resource_id(resource_id: &String) {
+    if !is_valid_resource_id(resource_id) {
+        panic!("Invalid resource id");
+    }
+}
+
+fn is_valid_resource_id(resource_id: &String) -> bool {
+    let len = resource_id.len() as usize;
+    if len == 0 || len > MAX_RESOURCE_ID_LEN {
+        return false;
+    }
+
+    let mut buf = [0u8; MAX_RESOURCE_ID_LEN];
+    resource_id.copy_into_slice(&mut buf[..len]);
+    let path = &buf[..len];
+
+    match path.iter().position(|b| *b == b'*') {
+        Some(star) => star == len - 1 && (star == 0 || path[star - 1] == b'/'),
+        None => true,
+    }
+}
+
+/// Mark every valid item of a rejected batch as not applied
+fn not_applied(env: &Env, outcomes: Vec<BatchOutcome>) -> Vec<BatchOutcome> {
+    let mut rejected: Vec<BatchOutcome> = Vec::new(env);
+    for outcome in outcomes.iter() {
+        rejected.push_back(if outcome == BatchOutcome::Applied {
+            BatchOutcome::NotApplied
+        } else {
+            outcome
+        });
+    }
+    rejected
+}
+
+fn is_wildcard(pattern: &String) -> bool {
+    let len = pattern.len() as usize;
+    if len == 0 || len > MAX_RESOURCE_ID_LEN {
+        return false;
+    }
+    let mut buf = [0u8; MAX_RESOURCE_ID_LEN];
+    pattern.copy_into_slice(&mut buf[..len]);
+    buf[len - 1] == b'*'
+}
+
+/// How specifically `pattern` matches `resource_id`, or `None` if it doesn't.
+/// An exact match outranks any wildcard; longer wildcard prefixes outrank shorter ones.
+fn match_specificity(pattern: &String, resource_id: &String) -> Option<u32> {
+    let pattern_len = pattern.len() as usize;
+    let resource_len = resource_id.len() as usize;
+    if pattern_len > MAX_RESOURCE_ID_LEN || resource_len > MAX_RESOURCE_ID_LEN {
+        return None;
+    }
+
+    let mut pattern_buf = [0u8; MAX_RESOURCE_ID_LEN];
+    let mut resource_buf = [0u8; MAX_RESOURCE_ID_LEN];
+    pattern.copy_into_slice(&mut pattern_buf[..pattern_len]);
+    resource_id.copy_into_slice(&mut resource_buf[..resource_len]);
+    let resource = &resource_buf[..resource_len];
+
+    match pattern_buf[..pattern_len].split_last() {
+        // "a/b/*" covers "a/b/c" and deeper, but not "a/b" itself
+        Some((b'*', prefix)) if resource.len() > prefix.len() && resource.starts_with(prefix) => {
+            Some(prefix.len() as u32)
+        }
+        Some((b'*', _)) => None,
+        _ if pattern_buf[..pattern_len] == *resource => Some(MAX_RESOURCE_ID_LEN as u32 + 1),
+        _ => None,
+    }
+}
+
+/// Drop `wallet`'s exclusions equal to `resource_id`, or covered by it when `covered` is set.
+/// Covered exclusions are kept while another of `wallet`'s wildcard grants covers them.
+fn remove_exclusions(env: &Env, wallet: &Address, resource_id: &String, covered: bool) {
+    let key = DataKey::Exclusions(wallet.clone());
+    let exclusions: Vec<AccessExclusion> = match env.storage().persistent().get(&key) {
+        Some(exclusions) => exclusions,
+        None => return,
+    };
+
+    let mut remaining: Vec<AccessExclusion> = Vec::new(env);
+    for exclusion in exclusions.iter() {
+        let matched = if covered {
+            match_specificity(resource_id, &exclusion.resource_id).is_some()
+                && !covering_patterns(env, &exclusion.resource_id)
+                    .iter()
+                    .skip(1)
+                    .any(|pattern| find_permission(env, wallet, &pattern).is_some())
+        } else {
+            exclusion.resource_id == *resource_id
+        };
+        if !matched {
+            remaining.push_back(exclusion);
+        }
+    }
+    env.storage().persistent().set(&key, &remaining);
+}
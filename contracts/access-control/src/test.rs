@@ -1,7 +1,9 @@
 #![cfg(test)]
 
+extern crate std;
+
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use soroban_sdk::{testutils::Address as _, vec, Address, BytesN, Env, String};
 
 #[test]
 fn test_initialize() {
@@ -103,13 +105,29 @@ fn test_grant_access() {
 
     // Hospital grants access to doctor for patient records
     let resource_id = String::from_str(&env, "patient-123-records");
-    client.grant_access(&hospital, &doctor, &resource_id, &0);
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
 
     // Check that doctor has access
-    assert!(client.check_access(&doctor, &resource_id));
+    assert!(client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
 
     // Check authorized parties
-    let authorized = client.get_authorized_parties(&resource_id);
+    let authorized = client.get_authorized_parties(&resource_id, &0, &10);
     assert_eq!(authorized.len(), 1);
 }
 
@@ -142,19 +160,40 @@ fn test_revoke_access() {
     );
 
     let resource_id = String::from_str(&env, "patient-123-records");
-    client.grant_access(&hospital, &doctor, &resource_id, &0);
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
 
     // Verify access exists
-    assert!(client.check_access(&doctor, &resource_id));
+    assert!(client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
 
     // Revoke access
     client.revoke_access(&hospital, &doctor, &resource_id);
 
     // Verify access is revoked
-    assert!(!client.check_access(&doctor, &resource_id));
+    assert!(!client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
 
     // Verify authorized parties is empty
-    let authorized = client.get_authorized_parties(&resource_id);
+    let authorized = client.get_authorized_parties(&resource_id, &0, &10);
     assert_eq!(authorized.len(), 0);
 }
 
@@ -190,16 +229,37 @@ fn test_check_access_expired() {
 
     // Grant access with expiration at timestamp 100
     let resource_id = String::from_str(&env, "patient-123-records");
-    client.grant_access(&hospital, &doctor, &resource_id, &100);
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &100,
+        &0,
+        &0,
+        &vec![&env],
+    );
 
     // Access should be valid before expiration
-    assert!(client.check_access(&doctor, &resource_id));
+    assert!(client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
 
     // Advance ledger time past expiration
     env.ledger().set_timestamp(200);
 
     // Access should now be denied (expired)
-    assert!(!client.check_access(&doctor, &resource_id));
+    assert!(!client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
 }
 
 #[test]
@@ -233,12 +293,34 @@ fn test_get_entity_permissions() {
     // Grant multiple access permissions
     let resource_1 = String::from_str(&env, "patient-123-records");
     let resource_2 = String::from_str(&env, "patient-456-records");
+    client.register_resource(&hospital, &resource_1);
+    client.register_resource(&hospital, &resource_2);
 
-    client.grant_access(&hospital, &doctor, &resource_1, &0);
-    client.grant_access(&hospital, &doctor, &resource_2, &0);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_1,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_2,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
 
     // Get all permissions for the doctor
-    let permissions = client.get_entity_permissions(&doctor);
+    let permissions = client.get_entity_permissions(&doctor, &0, &10);
     assert_eq!(permissions.len(), 2);
 }
 
@@ -262,7 +344,11 @@ fn test_deactivate_entity() {
     );
 
     // Deactivate the entity
-    client.deactivate_entity(&admin, &hospital);
+    client.deactivate_entity(
+        &admin,
+        &hospital,
+        &String::from_str(&env, "License suspended"),
+    );
 
     // Verify entity is deactivated
     let entity = client.get_entity(&hospital);
@@ -292,7 +378,11 @@ fn test_deactivate_entity_non_admin() {
     );
 
     // Non-admin tries to deactivate - should panic
-    client.deactivate_entity(&non_admin, &hospital);
+    client.deactivate_entity(
+        &non_admin,
+        &hospital,
+        &String::from_str(&env, "License suspended"),
+    );
 }
 
 #[test]
@@ -321,3 +411,2547 @@ fn test_update_entity() {
     let entity = client.get_entity(&hospital);
     assert_eq!(entity.metadata, new_metadata);
 }
+
+fn register(env: &Env, client: &AccessControlClient, wallet: &Address, entity_type: EntityType) {
+    client.register_entity(
+        wallet,
+        &entity_type,
+        &String::from_str(env, "name"),
+        &String::from_str(env, "metadata"),
+    );
+}
+
+/// Have a freshly appointed attester verify `wallet`'s declared type
+fn attest(
+    env: &Env,
+    client: &AccessControlClient,
+    admin: &Address,
+    wallet: &Address,
+    entity_type: EntityType,
+) {
+    let attester = Address::generate(env);
+    client.add_attester(admin, &attester);
+    client.attest_entity(
+        &attester,
+        wallet,
+        &entity_type,
+        &BytesN::from_array(env, &[1; 32]),
+    );
+}
+
+/// "patient/<address>/<suffix>", the namespace only `patient` may register
+fn patient_path(env: &Env, patient: &Address, suffix: &str) -> String {
+    let address = patient.to_string();
+    let mut buf = std::vec![0u8; address.len() as usize];
+    address.copy_into_slice(&mut buf);
+    let address = std::str::from_utf8(&buf).unwrap();
+    String::from_str(env, &std::format!("patient/{address}/{suffix}"))
+}
+
+#[test]
+fn test_role_grants_access_to_matching_resources() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let nurse = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &nurse, EntityType::Doctor);
+
+    // "lab_tech" may read and append any lab result, but nothing else
+    client.register_resource(
+        &hospital,
+        &String::from_str(&env, "city-hospital/lab-results/*"),
+    );
+    let role = String::from_str(&env, "lab_tech");
+    let rules = vec![
+        &env,
+        RoleRule {
+            resource_pattern: String::from_str(&env, "city-hospital/lab-results/*"),
+            actions: vec![&env, AccessAction::Read, AccessAction::Append],
+            purposes: vec![&env, Purpose::Treatment],
+        },
+    ];
+    client.define_role(&hospital, &role, &rules);
+    client.assign_role(&hospital, &nurse, &role);
+
+    assert!(client.check_access(
+        &nurse,
+        &String::from_str(&env, "city-hospital/lab-results/123"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(
+        &nurse,
+        &String::from_str(&env, "patient-123-records"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert_eq!(client.get_entity_roles(&nurse).len(), 1);
+
+    client.unassign_role(&hospital, &nurse, &role);
+    assert!(!client.check_access(
+        &nurse,
+        &String::from_str(&env, "city-hospital/lab-results/123"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+}
+
+#[test]
+fn test_removed_role_stops_granting_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let clerk = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &clerk, EntityType::Insurer);
+
+    let role = String::from_str(&env, "billing_clerk");
+    let invoice = String::from_str(&env, "invoice-42");
+    client.register_resource(&hospital, &invoice);
+    let rules = vec![
+        &env,
+        RoleRule {
+            resource_pattern: invoice.clone(),
+            actions: vec![&env, AccessAction::Read],
+            purposes: vec![&env, Purpose::Treatment],
+        },
+    ];
+    client.define_role(&hospital, &role, &rules);
+    client.assign_role(&hospital, &clerk, &role);
+    assert!(client.check_access(&clerk, &invoice, &AccessAction::Read, &Purpose::Treatment));
+
+    client.remove_role(&hospital, &role);
+    assert!(!client.check_access(&clerk, &invoice, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+#[should_panic(expected = "Role not found")]
+fn test_assign_undefined_role() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    // Roles are per scope: another hospital's role name is not usable here
+    client.assign_role(&hospital, &doctor, &String::from_str(&env, "attending"));
+}
+
+#[test]
+fn test_wildcard_grant_covers_children() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let radiologist = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &radiologist, EntityType::Doctor);
+
+    // Share all imaging without enumerating each record
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+    client.grant_access(
+        &patient,
+        &radiologist,
+        &patient_path(&env, &patient, "imaging/*"),
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    assert!(client.check_access(
+        &radiologist,
+        &patient_path(&env, &patient, "imaging/ct-1"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(client.check_access(
+        &radiologist,
+        &patient_path(&env, &patient, "imaging/2024/mri-7"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(
+        &radiologist,
+        &patient_path(&env, &patient, "imaging"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(
+        &radiologist,
+        &patient_path(&env, &patient, "imagingx"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(
+        &radiologist,
+        &patient_path(&env, &patient, "labs/cbc"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+}
+
+#[test]
+fn test_narrower_revoke_takes_precedence() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let all_records = patient_path(&env, &patient, "*");
+    let psych = patient_path(&env, &patient, "psych/*");
+    let session = patient_path(&env, &patient, "psych/session-1");
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+    client.grant_access(
+        &patient,
+        &doctor,
+        &all_records,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    // Carve psychiatric notes out of the broader grant
+    client.revoke_access(&patient, &doctor, &psych);
+    assert!(!client.check_access(&doctor, &session, &AccessAction::Read, &Purpose::Treatment));
+    assert!(client.check_access(
+        &doctor,
+        &patient_path(&env, &patient, "labs/cbc"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert_eq!(client.get_entity_exclusions(&doctor).len(), 1);
+
+    // A still narrower explicit grant outranks the exclusion
+    client.grant_access(
+        &patient,
+        &doctor,
+        &session,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    assert!(client.check_access(&doctor, &session, &AccessAction::Read, &Purpose::Treatment));
+    assert!(!client.check_access(
+        &doctor,
+        &patient_path(&env, &patient, "psych/session-2"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+
+    // Revoking the wildcard grant drops the exclusions carved out of it
+    client.revoke_access(&patient, &doctor, &all_records);
+    assert_eq!(client.get_entity_exclusions(&doctor).len(), 0);
+    assert!(!client.check_access(
+        &doctor,
+        &patient_path(&env, &patient, "labs/cbc"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+}
+
+#[test]
+fn test_exclusion_survives_while_a_broader_wildcard_remains() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let all_records = patient_path(&env, &patient, "*");
+    let imaging = patient_path(&env, &patient, "imaging/*");
+    let scan = patient_path(&env, &patient, "imaging/scan-1");
+    client.register_resource(&patient, &all_records);
+    for resource_id in [&all_records, &imaging] {
+        client.grant_access(
+            &patient,
+            &doctor,
+            resource_id,
+            &AccessAction::Read,
+            &Purpose::Treatment,
+            &0,
+            &0,
+            &0,
+            &vec![&env],
+        );
+    }
+
+    client.revoke_access(&patient, &doctor, &scan);
+    assert!(!client.check_access(&doctor, &scan, &AccessAction::Read, &Purpose::Treatment));
+
+    // The broader grant still covers the scan, so its exclusion must stay
+    client.revoke_access(&patient, &doctor, &imaging);
+    assert_eq!(client.get_entity_exclusions(&doctor).len(), 1);
+    assert!(!client.check_access(&doctor, &scan, &AccessAction::Read, &Purpose::Treatment));
+    assert!(client.check_access(
+        &doctor,
+        &patient_path(&env, &patient, "imaging/scan-2"),
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+
+    client.revoke_access(&patient, &doctor, &all_records);
+    assert_eq!(client.get_entity_exclusions(&doctor).len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Invalid resource id")]
+fn test_wildcard_must_be_final_segment() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    client.grant_access(
+        &patient,
+        &doctor,
+        &patient_path(&env, &patient, "img*"),
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+}
+
+#[test]
+fn test_access_levels() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let pharmacist = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &pharmacist, EntityType::Doctor);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+    let prescriptions = patient_path(&env, &patient, "prescriptions/*");
+    let script = patient_path(&env, &patient, "prescriptions/rx-1");
+    client.grant_access(
+        &patient,
+        &pharmacist,
+        &prescriptions,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    client.grant_access(
+        &patient,
+        &doctor,
+        &prescriptions,
+        &AccessAction::Update,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    // A pharmacist can read prescriptions but not append notes
+    assert!(client.check_access(
+        &pharmacist,
+        &script,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(
+        &pharmacist,
+        &script,
+        &AccessAction::Append,
+        &Purpose::Treatment
+    ));
+
+    // Higher levels include the lower ones, but not sharing
+    assert!(client.check_access(&doctor, &script, &AccessAction::Read, &Purpose::Treatment));
+    assert!(client.check_access(&doctor, &script, &AccessAction::Append, &Purpose::Treatment));
+    assert!(client.check_access(&doctor, &script, &AccessAction::Update, &Purpose::Treatment));
+    assert!(!client.check_access(&doctor, &script, &AccessAction::Share, &Purpose::Treatment));
+
+    assert_eq!(
+        client
+            .get_entity_permissions(&pharmacist, &0, &10)
+            .get(0)
+            .unwrap()
+            .level,
+        AccessAction::Read
+    );
+}
+
+#[test]
+fn test_role_rules_limit_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let lab_tech = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &lab_tech, EntityType::Doctor);
+
+    client.register_resource(
+        &hospital,
+        &String::from_str(&env, "city-hospital/lab-results/*"),
+    );
+    let role = String::from_str(&env, "lab_tech");
+    let rules = vec![
+        &env,
+        RoleRule {
+            resource_pattern: String::from_str(&env, "city-hospital/lab-results/*"),
+            actions: vec![&env, AccessAction::Append],
+            purposes: vec![&env, Purpose::Treatment],
+        },
+    ];
+    client.define_role(&hospital, &role, &rules);
+    client.assign_role(&hospital, &lab_tech, &role);
+
+    // Role actions are an explicit set, not a level
+    let result = String::from_str(&env, "city-hospital/lab-results/123");
+    assert!(client.check_access(
+        &lab_tech,
+        &result,
+        &AccessAction::Append,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(&lab_tech, &result, &AccessAction::Read, &Purpose::Treatment));
+    assert!(!client.check_access(
+        &lab_tech,
+        &result,
+        &AccessAction::Update,
+        &Purpose::Treatment
+    ));
+}
+
+#[test]
+fn test_delegated_resharing_cascades_on_revoke() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let specialist = Address::generate(&env);
+    let colleague = Address::generate(&env);
+    let resident = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &specialist, EntityType::Doctor);
+    register(&env, &client, &colleague, EntityType::Doctor);
+    register(&env, &client, &resident, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    client.register_resource(&patient, &records);
+    let imaging = patient_path(&env, &patient, "imaging/*");
+    let scan = patient_path(&env, &patient, "imaging/ct-1");
+
+    // Two hops of re-sharing allowed
+    client.grant_access(
+        &patient,
+        &specialist,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &1000,
+        &2,
+        &0,
+        &vec![&env],
+    );
+    client.delegate_access(
+        &specialist,
+        &colleague,
+        &imaging,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &1000,
+        &1,
+    );
+    client.delegate_access(
+        &colleague,
+        &resident,
+        &scan,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &500,
+        &0,
+    );
+
+    assert!(client.check_access(&resident, &scan, &AccessAction::Read, &Purpose::Treatment));
+    let permission = client
+        .get_entity_permissions(&resident, &0, &10)
+        .get(0)
+        .unwrap();
+    assert_eq!(permission.granted_by, colleague);
+    assert_eq!(permission.parent_resource, Some(imaging.clone()));
+
+    // Revoking the root grant removes everything derived from it
+    client.revoke_access(&patient, &specialist, &records);
+    assert!(!client.check_access(&colleague, &scan, &AccessAction::Read, &Purpose::Treatment));
+    assert!(!client.check_access(&resident, &scan, &AccessAction::Read, &Purpose::Treatment));
+    assert_eq!(client.get_entity_permissions(&resident, &0, &10).len(), 0);
+    assert_eq!(client.get_delegations(&specialist, &records).len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Delegation depth exceeded")]
+fn test_delegation_depth_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let specialist = Address::generate(&env);
+    let colleague = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &specialist, EntityType::Doctor);
+    register(&env, &client, &colleague, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    client.register_resource(&patient, &records);
+    client.grant_access(
+        &patient,
+        &specialist,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+        &0,
+        &vec![&env],
+    );
+
+    // One hop left means the colleague cannot be given a re-share right
+    client.delegate_access(
+        &specialist,
+        &colleague,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Delegated access cannot outlive the parent grant")]
+fn test_delegated_expiry_bounded_by_parent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let specialist = Address::generate(&env);
+    let colleague = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &specialist, EntityType::Doctor);
+    register(&env, &client, &colleague, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    client.register_resource(&patient, &records);
+    client.grant_access(
+        &patient,
+        &specialist,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &1000,
+        &1,
+        &0,
+        &vec![&env],
+    );
+
+    client.delegate_access(
+        &specialist,
+        &colleague,
+        &records,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+    );
+}
+
+#[test]
+fn test_break_glass_access_and_review() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let er_doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &er_doctor, EntityType::Doctor);
+    attest(&env, &client, &admin, &er_doctor, EntityType::Doctor);
+
+    let allergies = patient_path(&env, &patient, "allergies");
+    assert!(!client.check_access(
+        &er_doctor,
+        &allergies,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+    let id = client.break_glass(
+        &er_doctor,
+        &allergies,
+        &String::from_str(&env, "Unconscious on arrival"),
+    );
+    assert!(client.check_access(
+        &er_doctor,
+        &allergies,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(!client.check_access(
+        &er_doctor,
+        &allergies,
+        &AccessAction::Append,
+        &Purpose::Treatment
+    ));
+    assert_eq!(client.get_emergency_access(&id).owner, patient);
+    assert_eq!(client.get_pending_reviews(&1, &10), vec![&env, id]);
+
+    client.review_emergency_access(
+        &admin,
+        &id,
+        &ReviewOutcome::Approved,
+        &String::from_str(&env, "Justified"),
+    );
+    assert_eq!(client.get_pending_reviews(&1, &10).len(), 0);
+    assert_eq!(
+        client.get_emergency_review(&id).unwrap().outcome,
+        ReviewOutcome::Approved
+    );
+
+    // The access is short-lived
+    env.ledger().set_timestamp(4 * 3600);
+    assert!(!client.check_access(
+        &er_doctor,
+        &allergies,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+}
+
+#[test]
+fn test_flagged_break_glass_cuts_off_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    attest(&env, &client, &admin, &doctor, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "history");
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+    let id = client.break_glass(&doctor, &records, &String::from_str(&env, "curious"));
+
+    client.review_emergency_access(
+        &admin,
+        &id,
+        &ReviewOutcome::Flagged,
+        &String::from_str(&env, "No emergency"),
+    );
+    assert!(!client.check_access(&doctor, &records, &AccessAction::Read, &Purpose::Treatment));
+
+    // The record itself is kept for audit
+    assert_eq!(
+        client.get_emergency_access(&id).justification,
+        String::from_str(&env, "curious")
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only active, verified doctors can break glass")]
+fn test_break_glass_requires_doctor() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let insurer = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &insurer, EntityType::Insurer);
+
+    client.break_glass(
+        &insurer,
+        &patient_path(&env, &patient, "history"),
+        &String::from_str(&env, "claim"),
+    );
+}
+
+#[test]
+fn test_deactivated_entities_lose_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    // A struck-off doctor keeps no permissions
+    client.deactivate_entity(&admin, &doctor, &String::from_str(&env, "Struck off"));
+    assert!(!client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+
+    let entity = client.get_entity(&doctor);
+    assert_eq!(
+        entity.deactivation_reason,
+        String::from_str(&env, "Struck off")
+    );
+
+    client.reactivate_entity(&admin, &doctor);
+    assert!(client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    assert!(client.get_entity(&doctor).active);
+
+    // Grants made by a deactivated grantor stop counting too
+    client.deactivate_entity(&admin, &hospital, &String::from_str(&env, "Closed"));
+    assert!(!client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+}
+
+#[test]
+#[should_panic(expected = "Grantor is deactivated")]
+fn test_deactivated_grantor_cannot_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    client.deactivate_entity(&admin, &hospital, &String::from_str(&env, "Closed"));
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &String::from_str(&env, "patient-123-records"),
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only admin can reactivate entities")]
+fn test_reactivate_entity_non_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let doctor = Address::generate(&env);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    client.deactivate_entity(&admin, &doctor, &String::from_str(&env, "Struck off"));
+
+    client.reactivate_entity(&doctor, &doctor);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to grant access to this resource")]
+fn test_only_owner_can_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let other_patient = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &other_patient, EntityType::Patient);
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+
+    // Another patient cannot share p1's records
+    client.grant_access(
+        &other_patient,
+        &other_patient,
+        &patient_path(&env, &patient, "labs/cbc"),
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+}
+
+#[test]
+fn test_co_owner_and_admin_can_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let child = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &child, EntityType::Patient);
+    register(&env, &client, &guardian, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let records = patient_path(&env, &child, "*");
+    let vaccines = patient_path(&env, &child, "vaccines");
+    client.register_resource(&child, &records);
+    client.add_co_owner(&child, &records, &guardian);
+
+    client.grant_access(
+        &guardian,
+        &doctor,
+        &vaccines,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    assert!(client.check_access(&doctor, &vaccines, &AccessAction::Read, &Purpose::Treatment));
+    assert_eq!(client.get_resource(&vaccines).owner, child);
+
+    // The child, as owner, can revoke what the guardian granted
+    client.revoke_access(&child, &doctor, &vaccines);
+    assert!(!client.check_access(&doctor, &vaccines, &AccessAction::Read, &Purpose::Treatment));
+
+    client.grant_access(
+        &admin,
+        &doctor,
+        &vaccines,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    assert!(client.check_access(&doctor, &vaccines, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let old_hospital = Address::generate(&env);
+    let new_hospital = Address::generate(&env);
+    register(&env, &client, &old_hospital, EntityType::Hospital);
+    register(&env, &client, &new_hospital, EntityType::Hospital);
+
+    let archive = String::from_str(&env, "city-hospital/archive/*");
+    client.register_resource(&old_hospital, &archive);
+
+    client.propose_ownership_transfer(&old_hospital, &archive, &new_hospital);
+    assert_eq!(client.get_resource(&archive).owner, old_hospital);
+
+    client.accept_ownership_transfer(&new_hospital, &archive);
+    assert_eq!(client.get_resource(&archive).owner, new_hospital);
+
+    // The previous owner can no longer grant
+    let result = client.try_grant_access(
+        &old_hospital,
+        &new_hospital,
+        &String::from_str(&env, "city-hospital/archive/2020"),
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+#[should_panic(expected = "Resource owned by another entity")]
+fn test_cannot_register_inside_foreign_subtree() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let hospital = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+
+    client.register_resource(&hospital, &patient_path(&env, &patient, "imaging/*"));
+}
+
+#[test]
+fn test_access_requires_matching_purpose() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let insurer = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &insurer, EntityType::Insurer);
+
+    let resource_id = String::from_str(&env, "billing/2024");
+    client.register_resource(&patient, &resource_id);
+    client.grant_access(
+        &patient,
+        &insurer,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Payment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    assert!(client.check_access(
+        &insurer,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Payment
+    ));
+    assert!(!client.check_access(
+        &insurer,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Research
+    ));
+}
+
+#[test]
+fn test_list_and_revoke_consents_by_purpose() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let researcher = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    register(&env, &client, &researcher, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    let genome = patient_path(&env, &patient, "genome");
+    client.register_resource(&patient, &records);
+    client.grant_access(
+        &patient,
+        &doctor,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    client.grant_access(
+        &patient,
+        &researcher,
+        &genome,
+        &AccessAction::Share,
+        &Purpose::Research,
+        &0,
+        &1,
+        &0,
+        &vec![&env],
+    );
+
+    // The researcher re-shares within the same research consent
+    let assistant = Address::generate(&env);
+    register(&env, &client, &assistant, EntityType::Doctor);
+    client.delegate_access(
+        &researcher,
+        &assistant,
+        &genome,
+        &AccessAction::Read,
+        &Purpose::Research,
+        &0,
+        &0,
+    );
+
+    let research = client.get_consents_by_purpose(&patient, &Purpose::Research, &0, &10);
+    assert_eq!(research.len(), 1);
+    assert_eq!(research.get(0).unwrap().grantee, researcher);
+    assert_eq!(
+        client
+            .get_consents_by_purpose(&patient, &Purpose::Treatment, &0, &10)
+            .len(),
+        1
+    );
+
+    assert_eq!(
+        client.revoke_consents_by_purpose(&patient, &Purpose::Research, &10),
+        1
+    );
+    assert!(!client.check_access(
+        &researcher,
+        &genome,
+        &AccessAction::Read,
+        &Purpose::Research
+    ));
+    assert!(!client.check_access(&assistant, &genome, &AccessAction::Read, &Purpose::Research));
+    assert_eq!(
+        client
+            .get_consents_by_purpose(&patient, &Purpose::Research, &0, &10)
+            .len(),
+        0
+    );
+
+    // Treatment consent is untouched
+    assert!(client.check_access(&doctor, &genome, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+fn test_owner_lists_and_revokes_consents_from_any_grantor() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let researcher = Address::generate(&env);
+    let registry = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &guardian, EntityType::Patient);
+    register(&env, &client, &researcher, EntityType::Doctor);
+    register(&env, &client, &registry, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    let genome = patient_path(&env, &patient, "genome");
+    client.register_resource(&patient, &records);
+    client.add_co_owner(&patient, &records, &guardian);
+
+    // Research consents the patient did not give themselves
+    client.grant_access(
+        &guardian,
+        &researcher,
+        &genome,
+        &AccessAction::Read,
+        &Purpose::Research,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    client.grant_access(
+        &admin,
+        &registry,
+        &records,
+        &AccessAction::Read,
+        &Purpose::Research,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    client.grant_access(
+        &patient,
+        &doctor,
+        &records,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    assert_eq!(
+        client
+            .get_consents_by_purpose(&patient, &Purpose::Research, &0, &10)
+            .len(),
+        0
+    );
+    let research = client.get_resource_consents(&records, &Purpose::Research, &0, &10);
+    assert_eq!(research.len(), 2);
+
+    // Revoked a page at a time
+    assert_eq!(
+        client.revoke_resource_consents(&patient, &records, &Purpose::Research, &1),
+        1
+    );
+    assert_eq!(
+        client.revoke_resource_consents(&patient, &records, &Purpose::Research, &1),
+        1
+    );
+    assert_eq!(
+        client.revoke_resource_consents(&patient, &records, &Purpose::Research, &1),
+        0
+    );
+    assert!(!client.check_access(
+        &researcher,
+        &genome,
+        &AccessAction::Read,
+        &Purpose::Research
+    ));
+    assert!(!client.check_access(&registry, &genome, &AccessAction::Read, &Purpose::Research));
+    assert!(client.check_access(&doctor, &genome, &AccessAction::Read, &Purpose::Treatment));
+
+    // Someone else's grantee cannot withdraw the patient's consents
+    assert!(client
+        .try_revoke_resource_consents(&researcher, &records, &Purpose::Treatment, &10)
+        .is_err());
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to share this resource")]
+fn test_delegation_requires_same_purpose() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let analyst = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    register(&env, &client, &analyst, EntityType::Doctor);
+
+    let resource_id = patient_path(&env, &patient, "notes");
+    client.register_resource(&patient, &resource_id);
+    client.grant_access(
+        &patient,
+        &doctor,
+        &resource_id,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+        &0,
+        &vec![&env],
+    );
+
+    client.delegate_access(
+        &doctor,
+        &analyst,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Operations,
+        &0,
+        &0,
+    );
+}
+
+#[test]
+fn test_scheduled_access_windows() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let locum = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &locum, EntityType::Doctor);
+
+    let resource_id = String::from_str(&env, "city-hospital/ward-4/charts");
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &locum,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    // Monday 2024-01-01 00:00 UTC; the locum starts a week later, weekdays 08:00-18:00
+    let monday = 1_704_067_200;
+    let hour = 3600;
+    let day = 24 * hour;
+    let weekdays = AccessWindow {
+        days: 0b0011111,
+        start: 8 * hour as u32,
+        end: 18 * hour as u32,
+    };
+    client.set_access_schedule(
+        &hospital,
+        &locum,
+        &resource_id,
+        &(monday + 7 * day),
+        &vec![&env, weekdays],
+    );
+
+    let can_read = |time: u64| {
+        env.ledger().set_timestamp(time);
+        client.check_access(
+            &locum,
+            &resource_id,
+            &AccessAction::Read,
+            &Purpose::Treatment,
+        )
+    };
+    assert!(!can_read(monday + 9 * hour));
+    assert!(can_read(monday + 7 * day + 9 * hour));
+    assert!(!can_read(monday + 7 * day + 19 * hour));
+    assert!(!can_read(monday + 12 * day + 10 * hour)); // Saturday
+}
+
+#[test]
+fn test_overnight_access_window() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let nurse = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &nurse, EntityType::Doctor);
+
+    let resource_id = String::from_str(&env, "city-hospital/ward-4/charts");
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &nurse,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    // Friday night shift, 22:00 to 06:00 the next morning
+    let friday = 1_704_067_200 + 4 * 24 * 3600;
+    let night_shift = AccessWindow {
+        days: 1 << 4,
+        start: 22 * 3600,
+        end: 6 * 3600,
+    };
+    client.set_access_schedule(
+        &hospital,
+        &nurse,
+        &resource_id,
+        &0,
+        &vec![&env, night_shift],
+    );
+
+    let can_read = |time: u64| {
+        env.ledger().set_timestamp(time);
+        client.check_access(
+            &nurse,
+            &resource_id,
+            &AccessAction::Read,
+            &Purpose::Treatment,
+        )
+    };
+    assert!(!can_read(friday + 21 * 3600));
+    assert!(can_read(friday + 23 * 3600));
+    assert!(can_read(friday + 27 * 3600)); // Saturday 03:00
+    assert!(!can_read(friday + 47 * 3600)); // Saturday 23:00
+}
+
+#[test]
+#[should_panic(expected = "Invalid access window")]
+fn test_invalid_access_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let resource_id = String::from_str(&env, "city-hospital/ward-4/charts");
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    let window = AccessWindow {
+        days: 0b0011111,
+        start: 8 * 3600,
+        end: 25 * 3600,
+    };
+    client.set_access_schedule(&hospital, &doctor, &resource_id, &0, &vec![&env, window]);
+}
+
+#[test]
+fn test_batch_grant_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let nurse = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    register(&env, &client, &nurse, EntityType::Doctor);
+
+    let charts = String::from_str(&env, "city-hospital/ward-4/charts");
+    let labs = String::from_str(&env, "city-hospital/ward-4/labs");
+    client.register_resource(&hospital, &String::from_str(&env, "city-hospital/ward-4/*"));
+
+    let items = vec![
+        &env,
+        GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: charts.clone(),
+            expires_at: 0,
+        },
+        GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: labs.clone(),
+            expires_at: 0,
+        },
+        GrantRequest {
+            grantee: nurse.clone(),
+            resource_id: charts.clone(),
+            expires_at: 1000,
+        },
+    ];
+    let outcomes = client.batch_grant_access(
+        &hospital,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &vec![&env],
+        &items,
+    );
+    assert_eq!(
+        outcomes,
+        vec![
+            &env,
+            BatchOutcome::Applied,
+            BatchOutcome::Applied,
+            BatchOutcome::Applied
+        ]
+    );
+
+    assert!(client.check_access(&doctor, &labs, &AccessAction::Read, &Purpose::Treatment));
+    assert!(client.check_access(&nurse, &charts, &AccessAction::Read, &Purpose::Treatment));
+    assert_eq!(client.get_entity_permissions(&doctor, &0, &10).len(), 2);
+}
+
+#[test]
+fn test_full_batches_fit_the_ledger_write_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    client.register_resource(&hospital, &String::from_str(&env, "city-hospital/ward-4/*"));
+
+    let mut items = vec![&env];
+    let mut refs = vec![&env];
+    for _ in 0..4 {
+        let doctor = Address::generate(&env);
+        register(&env, &client, &doctor, EntityType::Doctor);
+        let charts = String::from_str(&env, "city-hospital/ward-4/charts");
+        items.push_back(GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: charts.clone(),
+            expires_at: 0,
+        });
+        refs.push_back(GrantRef {
+            grantee: doctor,
+            resource_id: charts,
+        });
+    }
+
+    // A transaction may write at most 50 ledger entries
+    client.batch_grant_access(
+        &hospital,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &vec![&env],
+        &items,
+    );
+    assert!(env.cost_estimate().resources().write_entries <= 50);
+
+    client.batch_revoke_access(&hospital, &refs);
+    assert!(env.cost_estimate().resources().write_entries <= 50);
+
+    items.push_back(items.get(0).unwrap());
+    let result = client.try_batch_grant_access(
+        &hospital,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &vec![&env],
+        &items,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_batch_grant_is_all_or_nothing() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let nurse = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    register(&env, &client, &nurse, EntityType::Doctor);
+
+    let charts = String::from_str(&env, "city-hospital/ward-4/charts");
+    client.register_resource(&hospital, &charts);
+
+    let items = vec![
+        &env,
+        GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: charts.clone(),
+            expires_at: 0,
+        },
+        GrantRequest {
+            grantee: stranger,
+            resource_id: charts.clone(),
+            expires_at: 0,
+        },
+        GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: String::from_str(&env, "other/*"),
+            expires_at: 0,
+        },
+        // Would expire before the batch's start time
+        GrantRequest {
+            grantee: nurse,
+            resource_id: charts.clone(),
+            expires_at: 500,
+        },
+    ];
+    let outcomes = client.batch_grant_access(
+        &hospital,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &1000,
+        &vec![&env],
+        &items,
+    );
+    assert_eq!(
+        outcomes,
+        vec![
+            &env,
+            BatchOutcome::NotApplied,
+            BatchOutcome::NotRegistered,
+            BatchOutcome::NotAuthorized,
+            BatchOutcome::InvalidSchedule
+        ]
+    );
+
+    let items = vec![
+        &env,
+        GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: charts.clone(),
+            expires_at: 0,
+        },
+        GrantRequest {
+            grantee: doctor.clone(),
+            resource_id: charts.clone(),
+            expires_at: 0,
+        },
+    ];
+    let outcomes = client.batch_grant_access(
+        &hospital,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &vec![&env],
+        &items,
+    );
+    assert_eq!(
+        outcomes,
+        vec![&env, BatchOutcome::NotApplied, BatchOutcome::Duplicate]
+    );
+
+    // Nothing was granted
+    assert!(!client.check_access(&doctor, &charts, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+fn test_batch_revoke_and_revoke_all_granted_by() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let nurse = Address::generate(&env);
+    let pharmacist = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    register(&env, &client, &nurse, EntityType::Doctor);
+    register(&env, &client, &pharmacist, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    let meds = patient_path(&env, &patient, "meds");
+    client.register_resource(&patient, &records);
+    client.grant_access(
+        &patient,
+        &doctor,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+        &0,
+        &vec![&env],
+    );
+    client.grant_access(
+        &patient,
+        &nurse,
+        &meds,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    client.delegate_access(
+        &doctor,
+        &pharmacist,
+        &meds,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+    );
+
+    // The doctor cannot revoke the patient's grant, so the batch is rejected as a whole
+    let items = vec![
+        &env,
+        GrantRef {
+            grantee: pharmacist.clone(),
+            resource_id: meds.clone(),
+        },
+        GrantRef {
+            grantee: nurse.clone(),
+            resource_id: meds.clone(),
+        },
+    ];
+    assert_eq!(
+        client.batch_revoke_access(&doctor, &items),
+        vec![&env, BatchOutcome::NotApplied, BatchOutcome::NotAuthorized]
+    );
+    assert!(client.check_access(&pharmacist, &meds, &AccessAction::Read, &Purpose::Treatment));
+
+    // The patient revokes both
+    assert_eq!(
+        client.batch_revoke_access(&patient, &items),
+        vec![&env, BatchOutcome::Applied, BatchOutcome::Applied]
+    );
+    assert!(!client.check_access(&nurse, &meds, &AccessAction::Read, &Purpose::Treatment));
+    assert!(!client.check_access(&pharmacist, &meds, &AccessAction::Read, &Purpose::Treatment));
+
+    // The doctor leaves: everything they granted goes. The patient can withdraw theirs too
+    client.delegate_access(
+        &doctor,
+        &pharmacist,
+        &meds,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+    );
+    assert_eq!(client.revoke_all_granted_by(&admin, &doctor, &10), 1);
+    assert!(!client.check_access(&pharmacist, &meds, &AccessAction::Read, &Purpose::Treatment));
+    assert_eq!(client.revoke_all_granted_by(&patient, &patient, &10), 1);
+    assert!(!client.check_access(&doctor, &meds, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+fn test_paginated_permissions_and_parties() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    client.register_resource(&hospital, &String::from_str(&env, "city-hospital/ward/*"));
+
+    let resources = [
+        String::from_str(&env, "city-hospital/ward/a"),
+        String::from_str(&env, "city-hospital/ward/b"),
+        String::from_str(&env, "city-hospital/ward/c"),
+        String::from_str(&env, "city-hospital/ward/d"),
+        String::from_str(&env, "city-hospital/ward/e"),
+    ];
+    for resource_id in resources.iter() {
+        client.grant_access(
+            &hospital,
+            &doctor,
+            resource_id,
+            &AccessAction::Read,
+            &Purpose::Treatment,
+            &0,
+            &0,
+            &0,
+            &vec![&env],
+        );
+    }
+    assert_eq!(client.get_permission_count(&doctor), 5);
+    assert_eq!(client.get_entity_permissions(&doctor, &0, &2).len(), 2);
+    assert_eq!(client.get_entity_permissions(&doctor, &4, &2).len(), 1);
+    assert_eq!(client.get_entity_permissions(&doctor, &5, &2).len(), 0);
+
+    // Revoking from the middle keeps every remaining grant reachable
+    client.revoke_access(&hospital, &doctor, &resources[1]);
+    client.revoke_access(&hospital, &doctor, &resources[0]);
+    assert_eq!(client.get_permission_count(&doctor), 3);
+    let remaining = client.get_entity_permissions(&doctor, &0, &10);
+    for resource_id in resources[2..].iter() {
+        assert!(remaining.iter().any(|p| p.resource_id == *resource_id));
+    }
+
+    assert_eq!(client.get_authorized_party_count(&resources[2]), 1);
+    assert_eq!(client.get_authorized_party_count(&resources[0]), 0);
+    assert_eq!(
+        client.get_authorized_parties(&resources[2], &0, &10),
+        vec![&env, doctor]
+    );
+}
+
+#[test]
+fn test_migrate_legacy_access_lists() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    // Entities and a grant written in the original layout, before levels, purposes
+    // and deactivation details existed
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let resource_id = String::from_str(&env, "patient-123-records");
+    let legacy = LegacyAccessPermission {
+        resource_id: resource_id.clone(),
+        granted_by: hospital.clone(),
+        granted_at: 0,
+        expires_at: 0,
+    };
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        for (wallet, entity_type) in [
+            (&hospital, EntityType::Hospital),
+            (&doctor, EntityType::Doctor),
+        ] {
+            let entity = LegacyEntityData {
+                entity_type,
+                name: String::from_str(&env, "name"),
+                metadata: String::from_str(&env, "metadata"),
+                active: true,
+            };
+            storage.set(&DataKey::Entity(wallet.clone()), &entity);
+        }
+        storage.set(&DataKey::AccessList(doctor.clone()), &vec![&env, legacy]);
+        storage.set(
+            &DataKey::ResourceAccess(resource_id.clone()),
+            &vec![&env, doctor.clone()],
+        );
+    });
+
+    // Legacy entity records are readable before migration
+    let entity = client.get_entity(&doctor);
+    assert!(entity.active);
+    assert_eq!(entity.deactivated_at, 0);
+    assert!(!client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+
+    let wallets = vec![&env, doctor.clone(), hospital.clone()];
+    assert_eq!(client.migrate_access_lists(&admin, &wallets), 1);
+    assert!(client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment
+    ));
+    // Legacy grants carry no level beyond reading
+    assert!(!client.check_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Append,
+        &Purpose::Treatment
+    ));
+    assert_eq!(
+        client.get_authorized_parties(&resource_id, &0, &10),
+        vec![&env, doctor.clone()]
+    );
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        let entity: EntityData = storage.get(&DataKey::Entity(doctor.clone())).unwrap();
+        assert!(entity.active);
+        assert!(!storage.has(&DataKey::AccessList(doctor.clone())));
+        assert!(!storage.has(&DataKey::ResourceAccess(resource_id.clone())));
+    });
+
+    // Running it again is a no-op
+    assert_eq!(client.migrate_access_lists(&admin, &wallets), 0);
+}
+
+#[test]
+fn test_access_resource_is_logged() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    register(&env, &client, &stranger, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    let labs = patient_path(&env, &patient, "labs");
+    client.register_resource(&patient, &records);
+    client.grant_access(
+        &patient,
+        &doctor,
+        &records,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    env.ledger().set_timestamp(1000);
+    assert!(client.access_resource(&doctor, &labs, &AccessAction::Read, &Purpose::Treatment));
+    env.ledger().set_timestamp(2000);
+    assert!(!client.access_resource(&stranger, &labs, &AccessAction::Read, &Purpose::Research));
+
+    // Both attempts land in the log of the patient's registered subtree
+    assert_eq!(client.get_access_log_len(&records), 2);
+    let log = client.get_access_log(&patient, &records, &0, &10);
+    assert_eq!(
+        log.get(0).unwrap(),
+        AccessLogEntry {
+            accessor: doctor,
+            resource_id: labs.clone(),
+            action: AccessAction::Read,
+            purpose: Purpose::Treatment,
+            allowed: true,
+            accessed_at: 1000,
+        }
+    );
+    let denied = log.get(1).unwrap();
+    assert_eq!(denied.accessor, stranger);
+    assert!(!denied.allowed);
+    assert_eq!(client.get_access_log(&patient, &records, &1, &10).len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to view this access log")]
+fn test_only_owner_reads_access_log() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    client.register_resource(&patient, &records);
+
+    client.get_access_log(&doctor, &records, &0, &10);
+}
+
+#[test]
+#[should_panic(expected = "Only admin can assign the Admin type")]
+fn test_cannot_self_register_as_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let impostor = Address::generate(&env);
+    register(&env, &client, &impostor, EntityType::Admin);
+}
+
+#[test]
+fn test_attested_entity_types() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let board = Address::generate(&env);
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &doctor, EntityType::Doctor);
+
+    let resource_id = String::from_str(&env, "city-hospital/ward-4/charts");
+    client.register_resource(&hospital, &resource_id);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    // Registrations start unverified
+    assert!(!client.is_verified(&doctor));
+    assert!(!client.check_verified_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &EntityType::Doctor
+    ));
+
+    client.add_attester(&admin, &board);
+    let evidence = BytesN::from_array(&env, &[7; 32]);
+    client.attest_entity(&board, &doctor, &EntityType::Doctor, &evidence);
+    assert!(client.is_verified(&doctor));
+    assert_eq!(
+        client.get_attestation(&doctor).unwrap().evidence_hash,
+        evidence
+    );
+    assert!(client.check_verified_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &EntityType::Doctor
+    ));
+    assert!(!client.check_verified_access(
+        &doctor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &EntityType::Hospital
+    ));
+
+    // Removing the attester withdraws the verifications it made
+    client.remove_attester(&admin, &board);
+    assert!(!client.is_verified(&doctor));
+
+    // The admin can promote an entity to Admin, which needs no attestation
+    client.set_entity_type(&admin, &hospital, &EntityType::Admin);
+    assert!(client.is_verified(&hospital));
+}
+
+#[test]
+fn test_legacy_self_registered_admin_is_not_verified() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let impostor = Address::generate(&env);
+    let resource_id = String::from_str(&env, "city-hospital/ward-4/charts");
+
+    // Registered itself as Admin before only the admin could assign that type
+    env.as_contract(&contract_id, || {
+        let entity = LegacyEntityData {
+            entity_type: EntityType::Admin,
+            name: String::from_str(&env, "name"),
+            metadata: String::from_str(&env, "metadata"),
+            active: true,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Entity(impostor.clone()), &entity);
+    });
+    client.grant_access(
+        &admin,
+        &impostor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+
+    assert!(!client.is_verified(&impostor));
+    assert!(!client.check_verified_access(
+        &impostor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &EntityType::Admin
+    ));
+
+    client.set_entity_type(&admin, &impostor, &EntityType::Admin);
+    assert!(client.is_verified(&impostor));
+    assert!(client.check_verified_access(
+        &impostor,
+        &resource_id,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &EntityType::Admin
+    ));
+
+    // Moving it to another type withdraws the assignment
+    client.set_entity_type(&admin, &impostor, &EntityType::Hospital);
+    env.as_contract(&contract_id, || {
+        assert!(!env
+            .storage()
+            .persistent()
+            .has(&DataKey::AdminAssigned(impostor.clone())));
+    });
+}
+
+#[test]
+#[should_panic(expected = "Entity type does not match registration")]
+fn test_attestation_must_match_declared_type() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let board = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    client.add_attester(&admin, &board);
+
+    client.attest_entity(
+        &board,
+        &doctor,
+        &EntityType::Hospital,
+        &BytesN::from_array(&env, &[7; 32]),
+    );
+}
+
+#[test]
+fn test_only_admin_registers_top_level_subtrees() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let attacker = Address::generate(&env);
+    register(&env, &client, &attacker, EntityType::Hospital);
+
+    let root = String::from_str(&env, "*");
+    let ward = String::from_str(&env, "ward/*");
+    assert!(client.try_register_resource(&attacker, &root).is_err());
+    assert!(client.try_register_resource(&attacker, &ward).is_err());
+
+    // Without a claimed root the attacker cannot grant itself anything unowned
+    let result = client.try_grant_access(
+        &attacker,
+        &attacker,
+        &String::from_str(&env, "ward/records/1"),
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+        &0,
+        &vec![&env],
+    );
+    assert!(result.is_err());
+
+    client.register_resource(&admin, &ward);
+    assert_eq!(client.get_resource(&ward).owner, admin);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to register this resource")]
+fn test_cannot_register_another_patients_namespace() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let victim = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    register(&env, &client, &victim, EntityType::Patient);
+    register(&env, &client, &attacker, EntityType::Patient);
+
+    client.register_resource(&attacker, &patient_path(&env, &victim, "*"));
+}
+
+#[test]
+#[should_panic(expected = "Only active, verified doctors can break glass")]
+fn test_break_glass_requires_verified_doctor() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let self_declared = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &self_declared, EntityType::Doctor);
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+
+    client.break_glass(
+        &self_declared,
+        &patient_path(&env, &patient, "history"),
+        &String::from_str(&env, "Unconscious on arrival"),
+    );
+}
+
+#[test]
+fn test_deactivated_root_grantor_cuts_off_delegates() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let specialist = Address::generate(&env);
+    let colleague = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &specialist, EntityType::Doctor);
+    register(&env, &client, &colleague, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    client.register_resource(&patient, &records);
+    let scan = patient_path(&env, &patient, "imaging/ct-1");
+
+    client.grant_access(
+        &patient,
+        &specialist,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+        &0,
+        &vec![&env],
+    );
+    client.delegate_access(
+        &specialist,
+        &colleague,
+        &scan,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+    );
+    assert!(client.check_access(&colleague, &scan, &AccessAction::Read, &Purpose::Treatment));
+
+    // The colleague's direct grantor is still active, but the patient at the root is not
+    client.deactivate_entity(&admin, &patient, &String::from_str(&env, "Deceased"));
+    assert!(!client.check_access(&specialist, &scan, &AccessAction::Read, &Purpose::Treatment));
+    assert!(!client.check_access(&colleague, &scan, &AccessAction::Read, &Purpose::Treatment));
+
+    client.reactivate_entity(&admin, &patient);
+    assert!(client.check_access(&colleague, &scan, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+fn test_delegator_exclusions_bind_delegates() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let specialist = Address::generate(&env);
+    let colleague = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &specialist, EntityType::Doctor);
+    register(&env, &client, &colleague, EntityType::Doctor);
+
+    let records = patient_path(&env, &patient, "*");
+    let psych = patient_path(&env, &patient, "psych/session-1");
+    let scan = patient_path(&env, &patient, "imaging/ct-1");
+    client.register_resource(&patient, &records);
+
+    client.grant_access(
+        &patient,
+        &specialist,
+        &records,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+        &0,
+        &vec![&env],
+    );
+    client.revoke_access(&patient, &specialist, &psych);
+
+    // Re-sharing the whole wildcard does not pass on what was carved out of it
+    client.delegate_access(
+        &specialist,
+        &colleague,
+        &records,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+    );
+    assert!(client.check_access(&colleague, &scan, &AccessAction::Read, &Purpose::Treatment));
+    assert!(!client.check_access(&colleague, &psych, &AccessAction::Read, &Purpose::Treatment));
+
+    // Nor does an exclusion added after the delegation leave the delegate with access
+    client.revoke_access(&patient, &specialist, &scan);
+    assert!(!client.check_access(&colleague, &scan, &AccessAction::Read, &Purpose::Treatment));
+}
+
+#[test]
+fn test_parent_schedule_bounds_delegated_access() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let hospital = Address::generate(&env);
+    let consultant = Address::generate(&env);
+    let registrar = Address::generate(&env);
+    register(&env, &client, &hospital, EntityType::Hospital);
+    register(&env, &client, &consultant, EntityType::Doctor);
+    register(&env, &client, &registrar, EntityType::Doctor);
+
+    let charts = String::from_str(&env, "city-hospital/ward-4/charts");
+    client.register_resource(&hospital, &charts);
+
+    // Monday 2024-01-01 00:00 UTC; the consultant works weekdays 08:00-18:00 from the start
+    let monday = 1_704_067_200;
+    let hour = 3600;
+    let weekdays = AccessWindow {
+        days: 0b0011111,
+        start: 8 * hour as u32,
+        end: 18 * hour as u32,
+    };
+    client.grant_access(
+        &hospital,
+        &consultant,
+        &charts,
+        &AccessAction::Share,
+        &Purpose::Treatment,
+        &0,
+        &1,
+        &0,
+        &vec![&env, weekdays],
+    );
+    env.ledger().set_timestamp(monday + 9 * hour);
+    client.delegate_access(
+        &consultant,
+        &registrar,
+        &charts,
+        &AccessAction::Read,
+        &Purpose::Treatment,
+        &0,
+        &0,
+    );
+
+    // The consultant may narrow the registrar's hours but not widen them
+    let mornings = AccessWindow {
+        days: 0b0000011,
+        start: 8 * hour as u32,
+        end: 12 * hour as u32,
+    };
+    client.set_access_schedule(&consultant, &registrar, &charts, &0, &vec![&env, mornings]);
+    let all_week = AccessWindow {
+        days: 0b1111111,
+        start: 8 * hour as u32,
+        end: 18 * hour as u32,
+    };
+    assert!(client
+        .try_set_access_schedule(&consultant, &registrar, &charts, &0, &vec![&env, all_week])
+        .is_err());
+    assert!(client
+        .try_set_access_schedule(&consultant, &registrar, &charts, &0, &vec![&env])
+        .is_err());
+
+    let can_read = |time: u64| {
+        env.ledger().set_timestamp(time);
+        client.check_access(
+            &registrar,
+            &charts,
+            &AccessAction::Read,
+            &Purpose::Treatment,
+        )
+    };
+    assert!(can_read(monday + 9 * hour));
+    assert!(!can_read(monday + 13 * hour));
+
+    // Tightening the consultant's schedule later reaches the registrar too
+    client.set_access_schedule(
+        &hospital,
+        &consultant,
+        &charts,
+        &0,
+        &vec![
+            &env,
+            AccessWindow {
+                days: 0b0011111,
+                start: 10 * hour as u32,
+                end: 18 * hour as u32,
+            },
+        ],
+    );
+    assert!(!can_read(monday + 9 * hour));
+    assert!(can_read(monday + 11 * hour));
+}
+
+#[test]
+fn test_break_glass_again_and_paged_review_queue() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient, EntityType::Patient);
+    register(&env, &client, &doctor, EntityType::Doctor);
+    attest(&env, &client, &admin, &doctor, EntityType::Doctor);
+
+    client.register_resource(&patient, &patient_path(&env, &patient, "*"));
+    let history = patient_path(&env, &patient, "history");
+    let allergies = patient_path(&env, &patient, "allergies");
+    let reason = String::from_str(&env, "Unconscious on arrival");
+
+    let first = client.break_glass(&doctor, &history, &reason);
+    client.break_glass(&doctor, &allergies, &reason);
+
+    // A second emergency on the same resource later on replaces the lapsed window
+    env.ledger().set_timestamp(5 * 3600);
+    assert!(!client.check_access(&doctor, &history, &AccessAction::Read, &Purpose::Treatment));
+    let again = client.break_glass(&doctor, &history, &reason);
+    assert!(client.check_access(&doctor, &history, &AccessAction::Read, &Purpose::Treatment));
+
+    // Flagging the stale record leaves the current one alone
+    client.review_emergency_access(
+        &admin,
+        &first,
+        &ReviewOutcome::Flagged,
+        &String::from_str(&env, "Not documented"),
+    );
+    assert!(client.check_access(&doctor, &history, &AccessAction::Read, &Purpose::Treatment));
+
+    assert_eq!(client.get_pending_reviews(&1, &2), vec![&env, 2]);
+    assert_eq!(client.get_pending_reviews(&3, &100), vec![&env, again]);
+    assert_eq!(client.get_pending_reviews(&4, &100).len(), 0);
+}
@@ -1,19 +1,109 @@
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, String, Vec,
+    contract, contractclient, contracterror, contractimpl, contracttype, panic_with_error,
+    symbol_short, token, Address, BytesN, Env, Map, String, Symbol, Val, Vec,
 };
 
+/// Seconds in a day, used to map timestamps onto weekly availability windows
+const DAY_SECONDS: u64 = 86_400;
+
+/// Shortest slot a doctor may publish, which bounds how many slots a day can hold
+const MIN_SLOT_DURATION: u64 = 15 * 60;
+
+/// Length assumed for appointments booked before durations were recorded
+const LEGACY_APPOINTMENT_DURATION: u64 = 30 * 60;
+
+/// Furthest ahead `get_expiring_institutions` will look in a single call
+const MAX_EXPIRY_QUERY_HORIZON: u64 = 90 * DAY_SECONDS;
+
+/// Widest range `get_free_slots` will enumerate in a single call
+const MAX_SLOT_QUERY_RANGE: u64 = 31 * DAY_SECONDS;
+
+/// Basis-point denominator for deposit forfeiture shares
+const BPS_DENOMINATOR: u32 = 10_000;
+
+/// How far ahead a provider cancellation looks for a rebooking slot
+const REBOOKING_SEARCH_RANGE: u64 = 14 * DAY_SECONDS;
+
+/// How long a patient keeps priority on the offered rebooking slot
+const REBOOKING_HOLD_PERIOD: u64 = 2 * DAY_SECONDS;
+
+/// How long a waitlisted patient has to claim an offered slot
+const WAITLIST_CLAIM_PERIOD: u64 = 2 * 3600;
+
+/// Most patients a single doctor's waitlist holds
+const MAX_WAITLIST_SIZE: u32 = 100;
+
+/// How long before an appointment's start the patient may check in
+const CHECK_IN_OPENS_BEFORE: u64 = 2 * 3600;
+
+/// Most occurrences a single appointment series may generate
+const MAX_SERIES_LENGTH: u32 = 52;
+
+/// Most appointments `query_appointments` returns per page
+const MAX_PAGE_SIZE: u32 = 50;
+
+/// Most index entries `query_appointments` inspects per call, matching or not
+const MAX_PAGE_SCAN: u32 = 200;
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct InstitutionData {
     pub name: String,
     pub license_id: String,
     pub metadata: String,
-    pub is_verified: bool,
+    pub is_verified: bool, // Mirrors `status == Verified` for existing readers
+    pub status: InstitutionStatus,
+    pub status_history: Vec<StatusChange>,
+    pub issuing_authority: String,
+    pub license_issued_at: u64,
+    pub license_expires_at: u64, // 0 while no license details have been recorded
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InstitutionStatus {
+    Pending,
+    Verified,
+    Suspended, // Temporarily unverified, can be reinstated
+    Revoked,   // Permanently unverified
+    Expired,   // Verified but past the license expiry; reported on read, never stored
+}
+
+/// Evidence of a renewed license, awaiting a verifier's approval
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LicenseRenewal {
+    pub evidence_hash: BytesN<32>, // Hash of the renewed license document
+    pub new_expires_at: u64,
+    pub submitted_at: u64,
+}
+
+/// One verifier decision on an institution
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusChange {
+    pub status: InstitutionStatus,
+    pub reason: String,
+    pub changed_by: Address,
+    pub changed_at: u64,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Appointment {
+    pub id: u64,
+    pub patient: Address,
+    pub doctor: Address,
+    pub datetime: u64,
+    pub duration: u64,
+    pub status: AppointmentStatus,
+    pub series_id: Option<u64>, // Set when booked through create_appointment_series
+}
+
+/// Appointment as stored before durations and series were recorded
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyAppointment {
     pub id: u64,
     pub patient: Address,
     pub doctor: Address,
@@ -27,19 +117,282 @@ pub enum AppointmentStatus {
     Scheduled,
     Canceled,
     Completed,
+    NoShow,
+    CheckedIn,
+    InProgress,
+}
+
+/// When an appointment moved through its in-person stages
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentTimeline {
+    pub checked_in_at: Option<u64>,
+    pub started_at: Option<u64>,
+    pub completed_at: Option<u64>,
+}
+
+/// Running wait-time totals for one doctor, in seconds
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WaitTimeMetrics {
+    pub waits_recorded: u32, // Appointments with both check-in and start times
+    pub total_wait: u64,     // Check-in to start
+    pub average_wait: u64,
+    pub consultations_recorded: u32, // Appointments with both start and completion times
+    pub total_consultation: u64,     // Start to completion
+    pub average_consultation: u64,
+}
+
+/// Which side of an appointment a user is queried as
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppointmentRole {
+    Patient,
+    Doctor,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentFilter {
+    pub role: AppointmentRole,
+    pub statuses: Vec<AppointmentStatus>, // Empty matches any status
+    pub from: Option<u64>,                // Earliest datetime, inclusive
+    pub to: Option<u64>,                  // Latest datetime, exclusive
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentPage {
+    pub appointments: Vec<Appointment>,
+    pub next_cursor: Option<u32>, // Pass back as `cursor` to continue; None when exhausted
+}
+
+/// A recurring weekly window in which a doctor accepts bookings (UTC)
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WeeklyWindow {
+    pub day_of_week: u32, // 0 = Monday ... 6 = Sunday
+    pub start_time: u32,  // Seconds after midnight
+    pub end_time: u32,    // Seconds after midnight, exclusive
+}
+
+/// A one-off change to a doctor's weekly template
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AvailabilityException {
+    pub start: u64,
+    pub end: u64,
+    pub available: bool, // true opens extra slots, false blocks time off
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DoctorAvailability {
+    pub slot_duration: u64,
+    pub weekly_windows: Vec<WeeklyWindow>,
+    pub exceptions: Vec<AvailabilityException>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimeSlot {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Per-doctor booking deposit rules, applied to appointments created while set
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositPolicy {
+    pub token: Address, // Stellar asset contract the deposit is paid in
+    pub amount: i128,
+    pub cancellation_notice: u64, // Cancelling at least this many seconds ahead is refunded in full
+    pub late_cancel_forfeit_bps: u32, // Share paid to the doctor on late cancellation
+    pub no_show_forfeit_bps: u32, // Share paid to the doctor on no-show
+}
+
+/// Deposit escrowed by the contract for one appointment
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentDeposit {
+    pub policy: DepositPolicy, // Snapshot taken at booking time
+    pub settled: bool,
+}
+
+/// Why an appointment was cancelled
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CancellationReason {
+    PatientRequest,
+    ProviderIllness,
+    ProviderUnavailable,
+    FacilityClosure,
+    Emergency,
+    Other,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CancellationRecord {
+    pub cancelled_by: Address,
+    pub reason: CancellationReason,
+    pub cancelled_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HoldKind {
+    Rebooking,     // Priority slot after a provider cancellation
+    WaitlistOffer, // Freed slot offered to a waitlisted patient
+}
+
+/// A slot reserved for one patient; other patients cannot book it until `expires_at`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SlotHold {
+    pub patient: Address,
+    pub slot: TimeSlot,
+    pub expires_at: u64,
+    pub kind: HoldKind,
+    pub cancelled_appointment: u64, // The cancelled booking behind this hold
+    pub passed_over: Vec<Address>,  // Waitlisted patients whose offer for this slot lapsed
+}
+
+/// A patient queued for a doctor, wanting any slot inside the window
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WaitlistEntry {
+    pub patient: Address,
+    pub window_start: u64,
+    pub window_end: u64,
+    pub joined_at: u64,
+}
+
+/// Registries AppointmentScheduling consults about doctors and institutions
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchedulingConfig {
+    pub admin: Address,
+    pub healthcare_registry: Address, // HealthcareRegistry contract
+    pub doctor_registry: Address,     // DoctorRegistry contract
+}
+
+/// Mirror of the doctor-registry profile, decoded from cross-contract calls
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DoctorProfileData {
+    pub name: String,
+    pub specialization: String,
+    pub institution_wallet: Address,
+    pub metadata: String,
+}
+
+#[contractclient(name = "DoctorRegistryClient")]
+pub trait DoctorRegistryInterface {
+    fn get_doctor_profile(env: Env, wallet: Address) -> DoctorProfileData;
+}
+
+/// How a series repeats; it ends at whichever of `count` and `until` comes first
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecurrenceRule {
+    pub interval: u64, // Seconds between occurrences, e.g. 604800 for weekly
+    pub count: u32,    // Number of occurrences, 0 for no limit
+    pub until: u64,    // Latest occurrence start, inclusive, 0 for no limit
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentSeries {
+    pub id: u64,
+    pub patient: Address,
+    pub doctor: Address,
+    pub first_datetime: u64,
+    pub rule: RecurrenceRule,
+    pub appointment_ids: Vec<u64>, // Occurrences that were booked
+}
+
+/// Why a single occurrence of a series could not be booked
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConflictReason {
+    SlotUnavailable,
+    SlotAlreadyBooked,
+    SlotHeld,
+    NoAvailabilityPublished,
+    InsufficientDeposit,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeriesConflict {
+    pub datetime: u64,
+    pub reason: ConflictReason,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeriesBooking {
+    pub series_id: u64,
+    pub booked: Vec<u64>,
+    pub conflicts: Vec<SeriesConflict>,
+}
+
+/// A pending request by one party to move an appointment
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RescheduleProposal {
+    pub proposed_by: Address,
+    pub new_datetime: u64,
+    pub reason: String,
+    pub proposed_at: u64,
+}
+
+/// One accepted change of an appointment's datetime
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RescheduleRecord {
+    pub previous_datetime: u64,
+    pub new_datetime: u64,
+    pub changed_by: Address,
+    pub accepted_by: Address,
+    pub reason: String,
+    pub changed_at: u64,
 }
 
 #[contracttype]
 pub enum DataKey {
     Inst(Address),
-    Admin, // To manage the 'verifier' role
+    Admin,              // To manage the 'verifier' role
+    PendingAdmin,       // Proposed admin awaiting acceptance
+    Verifiers,          // Vec<Address> allowed to approve institutions
+    Threshold,          // Approvals needed before an institution is verified
+    Approvals(Address), // Institution -> Vec<Address> of verifiers that approved it
+    Renewal(Address),
+    ExpiryDay(u64), // Day index -> Vec<Address> of institutions whose license expires that day
 }
 
 #[contracttype]
 pub enum AppointmentKey {
     Appointment(u64),
     AppointmentCounter,
-    UserAppointments(Address),
+    UserAppointments(Address), // Legacy Vec<u64> per user, drained by migrate_user_appointments
+    Availability(Address),
+    DoctorDay(Address, u64), // (doctor, day index) -> Vec<u64> of appointments starting that day
+    RescheduleProposal(u64),
+    RescheduleHistory(u64),
+    DepositPolicy(Address),
+    Deposit(u64),
+    Config,
+    Cancellation(u64),
+    Holds(Address), // Doctor -> Vec<SlotHold>
+    RoleAppointmentCount(Address, AppointmentRole),
+    RoleAppointment(Address, AppointmentRole, u32), // (user, role, position) -> appointment id
+    Waitlist(Address), // Doctor -> Vec<WaitlistEntry>, first come first served
+    SeriesCounter,
+    Series(u64),
+    Timeline(u64),
+    WaitMetrics(Address),
 }
 
 #[contracterror]
@@ -52,6 +405,28 @@ pub enum Error {
     AppointmentNotFound = 4,
     InvalidAppointmentStatus = 5,
     UnauthorizedAppointmentAction = 6,
+    NoAvailabilityPublished = 7,
+    InvalidAvailability = 8,
+    SlotUnavailable = 9,
+    SlotAlreadyBooked = 10,
+    InvalidTimeRange = 11,
+    NoPendingReschedule = 12,
+    InvalidDepositPolicy = 13,
+    AppointmentNotOver = 14,
+    AlreadyInitialized = 15,
+    NotInitialized = 16,
+    SlotHeld = 17,
+    InvalidPageSize = 18,
+    DoctorNotRegistered = 19,
+    InstitutionNotVerified = 20,
+    AlreadyOnWaitlist = 21,
+    NotOnWaitlist = 22,
+    WaitlistFull = 23,
+    OfferNotFound = 24,
+    InvalidRecurrence = 25,
+    SeriesNotFound = 26,
+    InsufficientDeposit = 27,
+    OutsideCheckInWindow = 28,
 }
 
 #[contract]
@@ -59,9 +434,124 @@ pub struct HealthcareRegistry;
 
 #[contractimpl]
 impl HealthcareRegistry {
-    // Set an admin/verifier during initialization
+    // Set an admin/verifier during initialization; the admin starts as the sole verifier
     pub fn init(env: Env, admin: Address) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        admin.require_auth();
+
         env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage()
+            .instance()
+            .set(&DataKey::Verifiers, &Vec::from_array(&env, [admin]));
+        env.storage().instance().set(&DataKey::Threshold, &1u32);
+    }
+
+    pub fn get_admin(env: Env) -> Address {
+        load_admin(&env)
+    }
+
+    /// First step of an admin handover; the new admin must accept
+    pub fn propose_admin(env: Env, admin: Address, new_admin: Address) {
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::PendingAdmin, &new_admin);
+
+        env.events()
+            .publish((symbol_short!("adm_prop"), admin), new_admin);
+    }
+
+    pub fn accept_admin(env: Env, new_admin: Address) {
+        new_admin.require_auth();
+
+        let pending: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingAdmin)
+            .expect("No pending admin");
+        if pending != new_admin {
+            panic!("Not the pending admin");
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        env.storage().instance().remove(&DataKey::PendingAdmin);
+
+        env.events().publish(
+            (symbol_short!("adm_acc"), new_admin),
+            symbol_short!("success"),
+        );
+    }
+
+    pub fn add_verifier(env: Env, admin: Address, verifier: Address) {
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let mut verifiers = load_verifiers(&env);
+        if verifiers.contains(&verifier) {
+            panic!("Already a verifier");
+        }
+        verifiers.push_back(verifier.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::Verifiers, &verifiers);
+
+        env.events().publish(
+            (symbol_short!("ver_add"), verifier),
+            symbol_short!("success"),
+        );
+    }
+
+    pub fn remove_verifier(env: Env, admin: Address, verifier: Address) {
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        let mut verifiers = load_verifiers(&env);
+        let index = verifiers.first_index_of(&verifier).expect("Not a verifier");
+        if verifiers.len() - 1 < load_threshold(&env) {
+            panic!("Would leave fewer verifiers than the threshold");
+        }
+        verifiers.remove(index);
+        env.storage()
+            .instance()
+            .set(&DataKey::Verifiers, &verifiers);
+
+        env.events().publish(
+            (symbol_short!("ver_rm"), verifier),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Number of verifier approvals (M of N) an institution needs to become verified
+    pub fn set_verification_threshold(env: Env, admin: Address, threshold: u32) {
+        admin.require_auth();
+        require_admin(&env, &admin);
+
+        if threshold == 0 || threshold > load_verifiers(&env).len() {
+            panic!("Invalid threshold");
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Threshold, &threshold);
+    }
+
+    pub fn get_verifiers(env: Env) -> Vec<Address> {
+        load_verifiers(&env)
+    }
+
+    pub fn get_verification_threshold(env: Env) -> u32 {
+        load_threshold(&env)
+    }
+
+    /// Verifiers that have approved a still-unverified institution so far
+    pub fn get_approvals(env: Env, wallet: Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Approvals(wallet))
+            .unwrap_or(Vec::new(&env))
     }
 
     pub fn register_institution(
@@ -83,6 +573,11 @@ impl HealthcareRegistry {
             license_id,
             metadata,
             is_verified: false,
+            status: InstitutionStatus::Pending,
+            status_history: Vec::new(&env),
+            issuing_authority: String::from_str(&env, ""),
+            license_issued_at: 0,
+            license_expires_at: 0,
         };
 
         env.storage().persistent().set(&key, &data);
@@ -94,114 +589,549 @@ impl HealthcareRegistry {
 
     pub fn get_institution(env: Env, wallet: Address) -> InstitutionData {
         let key = DataKey::Inst(wallet);
-        env.storage()
+        let mut data: InstitutionData = env
+            .storage()
             .persistent()
             .get(&key)
-            .expect("Institution not found")
+            .expect("Institution not found");
+
+        // An expired license lapses verification without any transaction
+        if data.status == InstitutionStatus::Verified && license_expired(&env, &data) {
+            data.status = InstitutionStatus::Expired;
+            data.is_verified = false;
+        }
+        data
     }
 
-    pub fn update_institution(env: Env, wallet: Address, metadata: String) {
+    /// Record the license validity window; locked once the institution is verified
+    pub fn set_license_details(
+        env: Env,
+        wallet: Address,
+        issuing_authority: String,
+        issued_at: u64,
+        expires_at: u64,
+    ) {
         wallet.require_auth();
 
         let key = DataKey::Inst(wallet.clone());
         let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
 
-        data.metadata = metadata;
-        env.storage().persistent().set(&key, &data);
-    }
-
-    pub fn verify_institution(env: Env, verifier: Address, wallet: Address) {
-        verifier.require_auth();
-
-        // Access Control: Check if caller is the admin
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        if verifier != admin {
-            panic!("Not authorized to verify");
+        if data.status != InstitutionStatus::Pending {
+            panic!("License details can only change before verification");
+        }
+        if expires_at <= issued_at {
+            panic!("Invalid license dates");
         }
 
-        let key = DataKey::Inst(wallet.clone());
-        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+        remove_from_expiry_day(&env, &wallet, data.license_expires_at);
+        add_to_expiry_day(&env, &wallet, expires_at);
 
-        data.is_verified = true;
+        data.issuing_authority = issuing_authority;
+        data.license_issued_at = issued_at;
+        data.license_expires_at = expires_at;
         env.storage().persistent().set(&key, &data);
     }
-}
 
-#[contract]
-pub struct AppointmentScheduling;
+    /// Submit the hash of a renewed license for a verifier to approve
+    pub fn submit_license_renewal(
+        env: Env,
+        wallet: Address,
+        evidence_hash: BytesN<32>,
+        new_expires_at: u64,
+    ) {
+        wallet.require_auth();
 
-#[contractimpl]
-impl AppointmentScheduling {
-    pub fn create_appointment(env: Env, patient: Address, doctor: Address, datetime: u64) -> u64 {
-        patient.require_auth();
+        let data: InstitutionData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Inst(wallet.clone()))
+            .expect("Not found");
 
-        // Get next appointment ID
-        let counter_key = AppointmentKey::AppointmentCounter;
-        let appointment_id = env.storage().persistent().get(&counter_key).unwrap_or(0u64) + 1;
+        if matches!(
+            data.status,
+            InstitutionStatus::Suspended | InstitutionStatus::Revoked
+        ) {
+            panic!("Institution is suspended or revoked");
+        }
+        if new_expires_at <= env.ledger().timestamp() || new_expires_at <= data.license_expires_at {
+            panic!("Invalid license dates");
+        }
 
-        // Create appointment
-        let appointment = Appointment {
-            id: appointment_id,
-            patient: patient.clone(),
-            doctor: doctor.clone(),
-            datetime,
-            status: AppointmentStatus::Scheduled,
+        let renewal = LicenseRenewal {
+            evidence_hash: evidence_hash.clone(),
+            new_expires_at,
+            submitted_at: env.ledger().timestamp(),
         };
-
-        // Store appointment
-        let appointment_key = AppointmentKey::Appointment(appointment_id);
         env.storage()
             .persistent()
-            .set(&appointment_key, &appointment);
+            .set(&DataKey::Renewal(wallet.clone()), &renewal);
 
-        // Update counter
-        env.storage()
-            .persistent()
-            .set(&counter_key, &appointment_id);
+        env.events()
+            .publish((symbol_short!("lic_sub"), wallet), evidence_hash);
+    }
 
-        // Add to patient's appointments
-        let patient_key = AppointmentKey::UserAppointments(patient.clone());
-        let mut patient_appointments: Vec<u64> = env
-            .storage()
-            .persistent()
-            .get(&patient_key)
-            .unwrap_or(Vec::new(&env));
-        patient_appointments.push_back(appointment_id);
-        env.storage()
-            .persistent()
-            .set(&patient_key, &patient_appointments);
+    pub fn approve_license_renewal(env: Env, verifier: Address, wallet: Address) {
+        verifier.require_auth();
+        require_verifier(&env, &verifier);
 
-        // Add to doctor's appointments
-        let doctor_key = AppointmentKey::UserAppointments(doctor.clone());
-        let mut doctor_appointments: Vec<u64> = env
+        let renewal_key = DataKey::Renewal(wallet.clone());
+        let renewal: LicenseRenewal = env
             .storage()
             .persistent()
-            .get(&doctor_key)
-            .unwrap_or(Vec::new(&env));
-        doctor_appointments.push_back(appointment_id);
-        env.storage()
-            .persistent()
-            .set(&doctor_key, &doctor_appointments);
+            .get(&renewal_key)
+            .expect("No pending renewal");
 
-        // Emit event
-        env.events().publish(
-            (symbol_short!("appt_cr"), appointment_id),
-            (patient, doctor),
-        );
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
 
-        appointment_id
-    }
+        // The institution may have been suspended or revoked since it submitted
+        if matches!(
+            data.status,
+            InstitutionStatus::Suspended | InstitutionStatus::Revoked
+        ) {
+            panic!("Institution is suspended or revoked");
+        }
 
-    pub fn cancel_appointment(env: Env, patient: Address, appointment_id: u64) {
-        patient.require_auth();
+        remove_from_expiry_day(&env, &wallet, data.license_expires_at);
+        add_to_expiry_day(&env, &wallet, renewal.new_expires_at);
 
-        let appointment_key = AppointmentKey::Appointment(appointment_id);
-        let mut appointment: Appointment = env
-            .storage()
-            .persistent()
-            .get(&appointment_key)
-            .ok_or(Error::AppointmentNotFound)
-            .unwrap();
+        data.license_issued_at = renewal.submitted_at;
+        data.license_expires_at = renewal.new_expires_at;
+        if data.status == InstitutionStatus::Verified {
+            let reason = String::from_str(&env, "license renewed");
+            set_institution_status(
+                &env,
+                &mut data,
+                InstitutionStatus::Verified,
+                reason,
+                verifier,
+            );
+        }
+        env.storage().persistent().set(&key, &data);
+        env.storage().persistent().remove(&renewal_key);
+
+        env.events()
+            .publish((symbol_short!("lic_renew"), wallet), renewal.new_expires_at);
+    }
+
+    pub fn get_license_renewal(env: Env, wallet: Address) -> Option<LicenseRenewal> {
+        env.storage().persistent().get(&DataKey::Renewal(wallet))
+    }
+
+    /// Verified institutions whose license expires between now and `within` seconds from now
+    pub fn get_expiring_institutions(env: Env, within: u64) -> Vec<Address> {
+        if within > MAX_EXPIRY_QUERY_HORIZON {
+            panic!("Horizon too long");
+        }
+
+        let now = env.ledger().timestamp();
+        let until = now + within;
+        let mut expiring = Vec::new(&env);
+        for day in (now / DAY_SECONDS)..=(until / DAY_SECONDS) {
+            let wallets: Vec<Address> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::ExpiryDay(day))
+                .unwrap_or(Vec::new(&env));
+            for wallet in wallets.iter() {
+                let data: InstitutionData = env
+                    .storage()
+                    .persistent()
+                    .get(&DataKey::Inst(wallet.clone()))
+                    .expect("Not found");
+                if data.status == InstitutionStatus::Verified
+                    && data.license_expires_at >= now
+                    && data.license_expires_at <= until
+                {
+                    expiring.push_back(wallet);
+                }
+            }
+        }
+        expiring
+    }
+
+    pub fn update_institution(env: Env, wallet: Address, metadata: String) {
+        wallet.require_auth();
+
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+
+        data.metadata = metadata;
+        env.storage().persistent().set(&key, &data);
+    }
+
+    pub fn verify_institution(env: Env, verifier: Address, wallet: Address) {
+        verifier.require_auth();
+        require_verifier(&env, &verifier);
+
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+
+        // Suspended institutions come back through reinstatement, revoked ones never do
+        if matches!(
+            data.status,
+            InstitutionStatus::Suspended | InstitutionStatus::Revoked
+        ) {
+            panic!("Institution is suspended or revoked");
+        }
+        if license_expired(&env, &data) {
+            panic!("License expired");
+        }
+
+        // Collect this verifier's approval; only verifiers still in the set count
+        let approvals_key = DataKey::Approvals(wallet.clone());
+        let mut approvals: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&approvals_key)
+            .unwrap_or(Vec::new(&env));
+        if approvals.contains(&verifier) {
+            panic!("Already approved");
+        }
+        approvals.push_back(verifier.clone());
+
+        let verifiers = load_verifiers(&env);
+        let mut current = 0;
+        for approver in approvals.iter() {
+            if verifiers.contains(&approver) {
+                current += 1;
+            }
+        }
+        if current < load_threshold(&env) {
+            env.storage().persistent().set(&approvals_key, &approvals);
+            env.events()
+                .publish((symbol_short!("inst_appr"), wallet), verifier);
+            return;
+        }
+        env.storage().persistent().remove(&approvals_key);
+
+        let reason = String::from_str(&env, "verified");
+        set_institution_status(
+            &env,
+            &mut data,
+            InstitutionStatus::Verified,
+            reason.clone(),
+            verifier,
+        );
+        env.storage().persistent().set(&key, &data);
+
+        env.events()
+            .publish((symbol_short!("inst_ver"), wallet), reason);
+    }
+
+    /// Temporarily withdraw verification, e.g. while an investigation is open
+    pub fn suspend_institution(env: Env, verifier: Address, wallet: Address, reason: String) {
+        verifier.require_auth();
+        require_verifier(&env, &verifier);
+
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+
+        if data.status != InstitutionStatus::Verified {
+            panic!("Only verified institutions can be suspended");
+        }
+
+        set_institution_status(
+            &env,
+            &mut data,
+            InstitutionStatus::Suspended,
+            reason.clone(),
+            verifier,
+        );
+        env.storage().persistent().set(&key, &data);
+
+        env.events()
+            .publish((symbol_short!("inst_susp"), wallet), reason);
+    }
+
+    /// Permanently withdraw verification; a revoked institution cannot be reinstated
+    pub fn revoke_institution(env: Env, verifier: Address, wallet: Address, reason: String) {
+        verifier.require_auth();
+        require_verifier(&env, &verifier);
+
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+
+        if data.status == InstitutionStatus::Revoked {
+            panic!("Institution already revoked");
+        }
+
+        set_institution_status(
+            &env,
+            &mut data,
+            InstitutionStatus::Revoked,
+            reason.clone(),
+            verifier,
+        );
+        env.storage().persistent().set(&key, &data);
+
+        env.events()
+            .publish((symbol_short!("inst_rev"), wallet), reason);
+    }
+
+    /// Restore verification to a suspended institution
+    pub fn reinstate_institution(env: Env, verifier: Address, wallet: Address, reason: String) {
+        verifier.require_auth();
+        require_verifier(&env, &verifier);
+
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+
+        if data.status != InstitutionStatus::Suspended {
+            panic!("Only suspended institutions can be reinstated");
+        }
+
+        set_institution_status(
+            &env,
+            &mut data,
+            InstitutionStatus::Verified,
+            reason.clone(),
+            verifier,
+        );
+        env.storage().persistent().set(&key, &data);
+
+        env.events()
+            .publish((symbol_short!("inst_rein"), wallet), reason);
+    }
+}
+
+fn license_expired(env: &Env, data: &InstitutionData) -> bool {
+    data.license_expires_at != 0 && env.ledger().timestamp() >= data.license_expires_at
+}
+
+fn add_to_expiry_day(env: &Env, wallet: &Address, expires_at: u64) {
+    let key = DataKey::ExpiryDay(expires_at / DAY_SECONDS);
+    let mut wallets: Vec<Address> = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    wallets.push_back(wallet.clone());
+    env.storage().persistent().set(&key, &wallets);
+}
+
+fn remove_from_expiry_day(env: &Env, wallet: &Address, expires_at: u64) {
+    if expires_at == 0 {
+        return;
+    }
+    let key = DataKey::ExpiryDay(expires_at / DAY_SECONDS);
+    let mut wallets: Vec<Address> = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    if let Some(index) = wallets.first_index_of(wallet) {
+        wallets.remove(index);
+        env.storage().persistent().set(&key, &wallets);
+    }
+}
+
+fn load_admin(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .expect("Not initialized")
+}
+
+fn require_admin(env: &Env, admin: &Address) {
+    if *admin != load_admin(env) {
+        panic!("Not authorized");
+    }
+}
+
+fn load_verifiers(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Verifiers)
+        .unwrap_or(Vec::new(env))
+}
+
+fn load_threshold(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::Threshold)
+        .unwrap_or(1)
+}
+
+// Access Control: Check if caller is in the verifier set
+fn require_verifier(env: &Env, verifier: &Address) {
+    if !load_verifiers(env).contains(verifier) {
+        panic!("Not authorized to verify");
+    }
+}
+
+fn set_institution_status(
+    env: &Env,
+    data: &mut InstitutionData,
+    status: InstitutionStatus,
+    reason: String,
+    changed_by: Address,
+) {
+    data.status = status;
+    data.is_verified = status == InstitutionStatus::Verified;
+    data.status_history.push_back(StatusChange {
+        status,
+        reason,
+        changed_by,
+        changed_at: env.ledger().timestamp(),
+    });
+}
+
+#[contract]
+pub struct AppointmentScheduling;
+
+#[contractimpl]
+impl AppointmentScheduling {
+    pub fn create_appointment(env: Env, patient: Address, doctor: Address, datetime: u64) -> u64 {
+        patient.require_auth();
+
+        // Only doctors of verified institutions can receive bookings
+        if let Err(err) = check_doctor_verified(&env, &doctor) {
+            panic_with_error!(&env, err);
+        }
+
+        roll_over_offers(&env, &doctor);
+        match book_appointment(&env, &patient, &doctor, datetime, None) {
+            Ok(appointment_id) => appointment_id,
+            Err(err) => panic_with_error!(&env, err),
+        }
+    }
+
+    /// Book the same slot repeatedly according to `rule`
+    ///
+    /// Occurrences that cannot be booked are reported in `conflicts` rather than
+    /// failing the whole series. Rules that only end by date stop after
+    /// `MAX_SERIES_LENGTH` occurrences.
+    pub fn create_appointment_series(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        first_datetime: u64,
+        rule: RecurrenceRule,
+    ) -> SeriesBooking {
+        patient.require_auth();
+
+        if rule.interval == 0
+            || (rule.count == 0 && rule.until == 0)
+            || rule.count > MAX_SERIES_LENGTH
+        {
+            panic_with_error!(&env, Error::InvalidRecurrence);
+        }
+
+        // A rule that only ends by date is cut off at the longest series allowed
+        let mut occurrences = Vec::new(&env);
+        let mut datetime = first_datetime;
+        while occurrences.len() < MAX_SERIES_LENGTH
+            && (rule.count == 0 || occurrences.len() < rule.count)
+            && (rule.until == 0 || datetime <= rule.until)
+        {
+            occurrences.push_back(datetime);
+            // Nothing past the end of time can be booked
+            datetime = match datetime.checked_add(rule.interval) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        if occurrences.is_empty() {
+            panic_with_error!(&env, Error::InvalidRecurrence);
+        }
+
+        if let Err(err) = check_doctor_verified(&env, &doctor) {
+            panic_with_error!(&env, err);
+        }
+        roll_over_offers(&env, &doctor);
+
+        let series_id: u64 = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::SeriesCounter)
+            .unwrap_or(0u64)
+            + 1;
+
+        let mut booked = Vec::new(&env);
+        let mut conflicts = Vec::new(&env);
+        for datetime in occurrences.iter() {
+            match book_appointment(&env, &patient, &doctor, datetime, Some(series_id)) {
+                Ok(appointment_id) => booked.push_back(appointment_id),
+                Err(err) => conflicts.push_back(SeriesConflict {
+                    datetime,
+                    reason: conflict_reason(&env, err),
+                }),
+            }
+        }
+
+        let series = AppointmentSeries {
+            id: series_id,
+            patient: patient.clone(),
+            doctor: doctor.clone(),
+            first_datetime,
+            rule,
+            appointment_ids: booked.clone(),
+        };
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Series(series_id), &series);
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::SeriesCounter, &series_id);
+
+        env.events().publish(
+            (symbol_short!("ser_cr"), series_id),
+            (patient, doctor, booked.len(), conflicts.len()),
+        );
+
+        SeriesBooking {
+            series_id,
+            booked,
+            conflicts,
+        }
+    }
+
+    /// Cancel every occurrence of the series that is still scheduled and not yet started
+    ///
+    /// Single occurrences can be cancelled with `cancel_appointment`. Returns how
+    /// many appointments were cancelled.
+    pub fn cancel_series(env: Env, patient: Address, series_id: u64) -> u32 {
+        patient.require_auth();
+
+        let series: AppointmentSeries = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::Series(series_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::SeriesNotFound));
+        if series.patient != patient {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+
+        let now = env.ledger().timestamp();
+        let mut cancelled = 0;
+        for appointment_id in series.appointment_ids.iter() {
+            let appointment = load_appointment(&env, appointment_id);
+            if appointment.status == AppointmentStatus::Scheduled && appointment.datetime > now {
+                cancel_for_patient(&env, appointment);
+                cancelled += 1;
+            }
+        }
+
+        env.events()
+            .publish((symbol_short!("ser_can"), series_id), (patient, cancelled));
+
+        cancelled
+    }
+
+    pub fn get_series(env: Env, series_id: u64) -> AppointmentSeries {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Series(series_id))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::SeriesNotFound))
+    }
+
+    pub fn cancel_appointment(env: Env, patient: Address, appointment_id: u64) {
+        patient.require_auth();
+
+        let appointment = find_appointment(&env, appointment_id)
+            .ok_or(Error::AppointmentNotFound)
+            .unwrap();
 
         // Only patient can cancel, and only if appointment is scheduled
         if appointment.patient != patient {
@@ -212,33 +1142,28 @@ impl AppointmentScheduling {
             panic!("Can only cancel scheduled appointments");
         }
 
-        appointment.status = AppointmentStatus::Canceled;
-        env.storage()
-            .persistent()
-            .set(&appointment_key, &appointment);
-
-        // Emit event
-        env.events()
-            .publish((symbol_short!("appt_can"), appointment_id), patient);
+        cancel_for_patient(&env, appointment);
     }
 
     pub fn complete_appointment(env: Env, doctor: Address, appointment_id: u64) {
         doctor.require_auth();
 
         let appointment_key = AppointmentKey::Appointment(appointment_id);
-        let mut appointment: Appointment = env
-            .storage()
-            .persistent()
-            .get(&appointment_key)
+        let mut appointment = find_appointment(&env, appointment_id)
             .ok_or(Error::AppointmentNotFound)
             .unwrap();
 
-        // Only doctor can complete, and only if appointment is scheduled
+        // Only doctor can complete, and only if appointment is scheduled or under way
         if appointment.doctor != doctor {
             panic!("Unauthorized to complete this appointment");
         }
 
-        if !matches!(appointment.status, AppointmentStatus::Scheduled) {
+        if !matches!(
+            appointment.status,
+            AppointmentStatus::Scheduled
+                | AppointmentStatus::CheckedIn
+                | AppointmentStatus::InProgress
+        ) {
             panic!("Can only complete scheduled appointments");
         }
 
@@ -247,31 +1172,1562 @@ impl AppointmentScheduling {
             .persistent()
             .set(&appointment_key, &appointment);
 
+        let now = env.ledger().timestamp();
+        let mut timeline = load_timeline(&env, appointment_id);
+        timeline.completed_at = Some(now);
+        save_timeline(&env, appointment_id, &timeline);
+        if let Some(started_at) = timeline.started_at {
+            record_wait_metrics(&env, &doctor, None, Some(now - started_at));
+        }
+
+        settle_deposit(&env, &appointment, |_| 0);
+
         // Emit event
         env.events()
             .publish((symbol_short!("appt_cmp"), appointment_id), doctor);
     }
 
-    pub fn get_appointments(env: Env, user: Address) -> Vec<Appointment> {
-        let user_key = AppointmentKey::UserAppointments(user);
-        let appointment_ids: Vec<u64> = env
-            .storage()
+    /// Record the patient's arrival, by the patient or the institution's front desk
+    ///
+    /// Accepted from `CHECK_IN_OPENS_BEFORE` ahead of the appointment until its slot ends.
+    pub fn check_in(env: Env, caller: Address, appointment_id: u64) {
+        caller.require_auth();
+
+        let mut appointment = load_appointment(&env, appointment_id);
+        if caller != appointment.patient
+            && !is_doctor_institution(&env, &appointment.doctor, &caller)
+        {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+        if appointment.status != AppointmentStatus::Scheduled {
+            panic_with_error!(&env, Error::InvalidAppointmentStatus);
+        }
+        let now = env.ledger().timestamp();
+        if now.saturating_add(CHECK_IN_OPENS_BEFORE) < appointment.datetime
+            || now >= appointment.datetime.saturating_add(appointment.duration)
+        {
+            panic_with_error!(&env, Error::OutsideCheckInWindow);
+        }
+
+        appointment.status = AppointmentStatus::CheckedIn;
+        env.storage()
             .persistent()
-            .get(&user_key)
-            .unwrap_or(Vec::new(&env));
+            .set(&AppointmentKey::Appointment(appointment_id), &appointment);
 
-        let mut appointments = Vec::new(&env);
-        for id in appointment_ids.iter() {
-            if let Some(appointment) = env
-                .storage()
-                .persistent()
-                .get(&AppointmentKey::Appointment(id))
-            {
-                appointments.push_back(appointment);
-            }
+        let mut timeline = load_timeline(&env, appointment_id);
+        timeline.checked_in_at = Some(env.ledger().timestamp());
+        save_timeline(&env, appointment_id, &timeline);
+
+        env.events()
+            .publish((symbol_short!("appt_chk"), appointment_id), caller);
+    }
+
+    /// Doctor starts seeing the patient
+    pub fn start_appointment(env: Env, doctor: Address, appointment_id: u64) {
+        doctor.require_auth();
+
+        let mut appointment = load_appointment(&env, appointment_id);
+        if appointment.doctor != doctor {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+        if !matches!(
+            appointment.status,
+            AppointmentStatus::Scheduled | AppointmentStatus::CheckedIn
+        ) {
+            panic_with_error!(&env, Error::InvalidAppointmentStatus);
         }
 
-        appointments
+        appointment.status = AppointmentStatus::InProgress;
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Appointment(appointment_id), &appointment);
+
+        let now = env.ledger().timestamp();
+        let mut timeline = load_timeline(&env, appointment_id);
+        timeline.started_at = Some(now);
+        save_timeline(&env, appointment_id, &timeline);
+        if let Some(checked_in_at) = timeline.checked_in_at {
+            record_wait_metrics(&env, &doctor, Some(now - checked_in_at), None);
+        }
+
+        env.events()
+            .publish((symbol_short!("appt_st"), appointment_id), doctor);
+    }
+
+    pub fn get_timeline(env: Env, appointment_id: u64) -> AppointmentTimeline {
+        load_timeline(&env, appointment_id)
+    }
+
+    pub fn get_wait_metrics(env: Env, doctor: Address) -> WaitTimeMetrics {
+        load_wait_metrics(&env, &doctor)
+    }
+
+    /// Mark a patient as not having shown up once the slot has passed
+    ///
+    /// Any deposit is split according to the policy's no-show share.
+    pub fn mark_no_show(env: Env, doctor: Address, appointment_id: u64) {
+        doctor.require_auth();
+
+        let mut appointment = load_appointment(&env, appointment_id);
+        if appointment.doctor != doctor {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+        if appointment.status != AppointmentStatus::Scheduled {
+            panic_with_error!(&env, Error::InvalidAppointmentStatus);
+        }
+        if env.ledger().timestamp() < appointment.datetime + appointment.duration {
+            panic_with_error!(&env, Error::AppointmentNotOver);
+        }
+
+        appointment.status = AppointmentStatus::NoShow;
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Appointment(appointment_id), &appointment);
+
+        settle_deposit(&env, &appointment, |policy| policy.no_show_forfeit_bps);
+
+        env.events()
+            .publish((symbol_short!("appt_ns"), appointment_id), doctor);
+    }
+
+    /// Require a deposit for new bookings; existing appointments keep their terms
+    pub fn set_deposit_policy(env: Env, doctor: Address, policy: DepositPolicy) {
+        doctor.require_auth();
+
+        if policy.amount <= 0
+            || policy.late_cancel_forfeit_bps > BPS_DENOMINATOR
+            || policy.no_show_forfeit_bps > BPS_DENOMINATOR
+        {
+            panic_with_error!(&env, Error::InvalidDepositPolicy);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::DepositPolicy(doctor.clone()), &policy);
+
+        env.events().publish(
+            (symbol_short!("dep_pol"), doctor),
+            (policy.token, policy.amount),
+        );
+    }
+
+    pub fn remove_deposit_policy(env: Env, doctor: Address) {
+        doctor.require_auth();
+
+        env.storage()
+            .persistent()
+            .remove(&AppointmentKey::DepositPolicy(doctor.clone()));
+
+        env.events()
+            .publish((symbol_short!("dep_rm"), doctor), symbol_short!("success"));
+    }
+
+    pub fn get_deposit_policy(env: Env, doctor: Address) -> Option<DepositPolicy> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::DepositPolicy(doctor))
+    }
+
+    pub fn get_deposit(env: Env, appointment_id: u64) -> Option<AppointmentDeposit> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Deposit(appointment_id))
+    }
+
+    /// Every appointment of `user` in either role
+    ///
+    /// Reads one entry per appointment; busy users should page with `query_appointments`.
+    pub fn get_appointments(env: Env, user: Address) -> Vec<Appointment> {
+        let user_key = AppointmentKey::UserAppointments(user.clone());
+        let mut appointment_ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&user_key)
+            .unwrap_or(Vec::new(&env));
+
+        for role in [AppointmentRole::Patient, AppointmentRole::Doctor] {
+            for position in 0..user_index_len(&env, &user, role) {
+                if let Some(id) = env
+                    .storage()
+                    .persistent()
+                    .get(&AppointmentKey::RoleAppointment(
+                        user.clone(),
+                        role,
+                        position,
+                    ))
+                {
+                    appointment_ids.push_back(id);
+                }
+            }
+        }
+
+        let mut appointments = Vec::new(&env);
+        for id in appointment_ids.iter() {
+            if let Some(appointment) = find_appointment(&env, id) {
+                appointments.push_back(appointment);
+            }
+        }
+
+        appointments
+    }
+
+    /// Page through a user's appointments in the order they joined the user's index:
+    /// booking order, except that migrated legacy appointments follow any booked before
+    /// `migrate_user_appointments` moved them
+    ///
+    /// Inspects at most `MAX_PAGE_SCAN` entries per call, so a page may hold fewer
+    /// than `limit` matches while `next_cursor` is still set.
+    pub fn query_appointments(
+        env: Env,
+        user: Address,
+        filter: AppointmentFilter,
+        cursor: u32,
+        limit: u32,
+    ) -> AppointmentPage {
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            panic_with_error!(&env, Error::InvalidPageSize);
+        }
+
+        let total = user_index_len(&env, &user, filter.role);
+        let scan_end = core::cmp::min(total, cursor.saturating_add(MAX_PAGE_SCAN));
+
+        let mut appointments = Vec::new(&env);
+        let mut position = cursor;
+        while position < scan_end && appointments.len() < limit {
+            let key = AppointmentKey::RoleAppointment(user.clone(), filter.role, position);
+            position += 1;
+
+            let id: u64 = match env.storage().persistent().get(&key) {
+                Some(id) => id,
+                None => continue,
+            };
+            let appointment: Appointment = match find_appointment(&env, id) {
+                Some(appointment) => appointment,
+                None => continue,
+            };
+            if matches_filter(&appointment, &filter) {
+                appointments.push_back(appointment);
+            }
+        }
+
+        AppointmentPage {
+            appointments,
+            next_cursor: if position < total {
+                Some(position)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Move up to `limit` ids from the legacy per-user vector into the paged index
+    ///
+    /// Anyone may call this; it only relocates existing entries, rewriting appointments
+    /// stored before durations were recorded. Returns how many legacy ids remain.
+    pub fn migrate_user_appointments(env: Env, user: Address, limit: u32) -> u32 {
+        let user_key = AppointmentKey::UserAppointments(user.clone());
+        let legacy: Vec<u64> = match env.storage().persistent().get(&user_key) {
+            Some(legacy) => legacy,
+            None => return 0,
+        };
+
+        let batch = core::cmp::min(limit, legacy.len());
+        for id in legacy.slice(0..batch).iter() {
+            let appointment: Appointment = match find_appointment(&env, id) {
+                Some(appointment) => appointment,
+                None => continue,
+            };
+            // Store it in the current layout so it no longer needs upgrading on read
+            env.storage()
+                .persistent()
+                .set(&AppointmentKey::Appointment(id), &appointment);
+            // Legacy bookings were never filed by day, so overlap checks could not see them.
+            // Both the patient's and the doctor's vectors list each one, so file it once.
+            if occupies_slot(&appointment.status) {
+                let filed: Vec<u64> = env
+                    .storage()
+                    .persistent()
+                    .get(&AppointmentKey::DoctorDay(
+                        appointment.doctor.clone(),
+                        appointment.datetime / DAY_SECONDS,
+                    ))
+                    .unwrap_or(Vec::new(&env));
+                if !filed.contains(id) {
+                    add_to_doctor_day(&env, &appointment.doctor, appointment.datetime, id);
+                }
+            }
+            if appointment.patient == user {
+                add_to_user_index(&env, &user, AppointmentRole::Patient, id);
+            }
+            if appointment.doctor == user {
+                add_to_user_index(&env, &user, AppointmentRole::Doctor, id);
+            }
+        }
+
+        let remaining = legacy.slice(batch..);
+        if remaining.is_empty() {
+            env.storage().persistent().remove(&user_key);
+        } else {
+            env.storage().persistent().set(&user_key, &remaining);
+        }
+
+        remaining.len()
+    }
+
+    /// Publish the weekly template a doctor accepts bookings in
+    ///
+    /// Replaces any previous template; one-off exceptions are kept.
+    pub fn set_availability(
+        env: Env,
+        doctor: Address,
+        slot_duration: u64,
+        weekly_windows: Vec<WeeklyWindow>,
+    ) {
+        doctor.require_auth();
+
+        if !(MIN_SLOT_DURATION..=DAY_SECONDS).contains(&slot_duration) {
+            panic_with_error!(&env, Error::InvalidAvailability);
+        }
+        for window in weekly_windows.iter() {
+            if window.day_of_week > 6
+                || window.start_time >= window.end_time
+                || u64::from(window.end_time) > DAY_SECONDS
+            {
+                panic_with_error!(&env, Error::InvalidAvailability);
+            }
+        }
+
+        let key = AppointmentKey::Availability(doctor.clone());
+        let exceptions = env
+            .storage()
+            .persistent()
+            .get::<_, DoctorAvailability>(&key)
+            .map(|availability| availability.exceptions)
+            .unwrap_or(Vec::new(&env));
+
+        let availability = DoctorAvailability {
+            slot_duration,
+            weekly_windows,
+            exceptions,
+        };
+        env.storage().persistent().set(&key, &availability);
+
+        env.events()
+            .publish((symbol_short!("avail_set"), doctor), slot_duration);
+    }
+
+    /// Open extra slots or block time off outside the weekly template
+    ///
+    /// Exceptions that have already ended are pruned on every call.
+    pub fn add_availability_exception(env: Env, doctor: Address, exception: AvailabilityException) {
+        doctor.require_auth();
+
+        if exception.start >= exception.end {
+            panic_with_error!(&env, Error::InvalidTimeRange);
+        }
+
+        let key = AppointmentKey::Availability(doctor.clone());
+        let mut availability = load_availability(&env, &doctor);
+
+        let now = env.ledger().timestamp();
+        let mut exceptions = Vec::new(&env);
+        for existing in availability.exceptions.iter() {
+            if existing.end > now {
+                exceptions.push_back(existing);
+            }
+        }
+        exceptions.push_back(exception.clone());
+        availability.exceptions = exceptions;
+        env.storage().persistent().set(&key, &availability);
+
+        env.events().publish(
+            (symbol_short!("avail_exc"), doctor),
+            (exception.start, exception.end, exception.available),
+        );
+    }
+
+    /// Remove every exception starting at `start`
+    pub fn remove_availability_exception(env: Env, doctor: Address, start: u64) {
+        doctor.require_auth();
+
+        let key = AppointmentKey::Availability(doctor.clone());
+        let mut availability = load_availability(&env, &doctor);
+
+        let mut exceptions = Vec::new(&env);
+        for existing in availability.exceptions.iter() {
+            if existing.start != start {
+                exceptions.push_back(existing);
+            }
+        }
+        availability.exceptions = exceptions;
+        env.storage().persistent().set(&key, &availability);
+
+        env.events()
+            .publish((symbol_short!("avail_rm"), doctor), start);
+    }
+
+    pub fn get_availability(env: Env, doctor: Address) -> DoctorAvailability {
+        load_availability(&env, &doctor)
+    }
+
+    /// List the doctor's published slots in `[from, to)` that are not blocked or booked
+    pub fn get_free_slots(env: Env, doctor: Address, from: u64, to: u64) -> Vec<TimeSlot> {
+        if from >= to || to - from > MAX_SLOT_QUERY_RANGE {
+            panic_with_error!(&env, Error::InvalidTimeRange);
+        }
+
+        let availability = load_availability(&env, &doctor);
+        let holds = active_holds(&env, &doctor);
+        let duration = availability.slot_duration;
+
+        let mut free = Vec::new(&env);
+        for day in (from / DAY_SECONDS)..=((to - 1) / DAY_SECONDS) {
+            for slot in day_slots(&env, &availability, day).iter() {
+                if slot.start < from || slot.start + duration > to {
+                    continue;
+                }
+                if is_slot_free(&env, &doctor, &availability, &holds, &slot) {
+                    free.push_back(slot);
+                }
+            }
+        }
+
+        free
+    }
+
+    /// Point AppointmentScheduling at the registries it consults; can only be called once
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        healthcare_registry: Address,
+        doctor_registry: Address,
+    ) {
+        if env.storage().instance().has(&AppointmentKey::Config) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+
+        let config = SchedulingConfig {
+            admin: admin.clone(),
+            healthcare_registry,
+            doctor_registry,
+        };
+        env.storage()
+            .instance()
+            .set(&AppointmentKey::Config, &config);
+
+        env.events()
+            .publish((symbol_short!("init"), admin), symbol_short!("success"));
+    }
+
+    pub fn get_config(env: Env) -> SchedulingConfig {
+        load_config(&env)
+    }
+
+    /// Cancel on the provider's side, by the doctor or the doctor's institution
+    ///
+    /// The deposit is refunded in full and the patient is given a priority hold
+    /// on the doctor's next free slot.
+    pub fn cancel_appointment_by_provider(
+        env: Env,
+        caller: Address,
+        appointment_id: u64,
+        reason: CancellationReason,
+    ) {
+        caller.require_auth();
+
+        let mut appointment = load_appointment(&env, appointment_id);
+        if caller != appointment.doctor
+            && !is_doctor_institution(&env, &appointment.doctor, &caller)
+        {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+        if appointment.status != AppointmentStatus::Scheduled {
+            panic_with_error!(&env, Error::InvalidAppointmentStatus);
+        }
+
+        appointment.status = AppointmentStatus::Canceled;
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Appointment(appointment_id), &appointment);
+
+        let now = env.ledger().timestamp();
+        let record = CancellationRecord {
+            cancelled_by: caller.clone(),
+            reason,
+            cancelled_at: now,
+        };
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Cancellation(appointment_id), &record);
+
+        settle_deposit(&env, &appointment, |_| 0);
+
+        env.events().publish(
+            (symbol_short!("appt_pcan"), appointment_id),
+            (caller, reason),
+        );
+
+        // Offer the patient the first free slot after the cancelled one
+        roll_over_offers(&env, &appointment.doctor);
+        let after = core::cmp::max(now, appointment.datetime + appointment.duration);
+        if let Some(slot) = next_free_slot(&env, &appointment.doctor, after) {
+            let hold = SlotHold {
+                patient: appointment.patient.clone(),
+                slot: slot.clone(),
+                expires_at: core::cmp::min(slot.start, now + REBOOKING_HOLD_PERIOD),
+                kind: HoldKind::Rebooking,
+                cancelled_appointment: appointment_id,
+                passed_over: Vec::new(&env),
+            };
+            let mut holds = active_holds(&env, &appointment.doctor);
+            holds.push_back(hold.clone());
+            env.storage()
+                .persistent()
+                .set(&AppointmentKey::Holds(appointment.doctor.clone()), &holds);
+
+            env.events().publish(
+                (symbol_short!("hold"), appointment_id),
+                (appointment.patient, slot.start, hold.expires_at),
+            );
+        }
+    }
+
+    pub fn get_cancellation(env: Env, appointment_id: u64) -> Option<CancellationRecord> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Cancellation(appointment_id))
+    }
+
+    /// Unexpired rebooking holds and waitlist offers on the doctor's calendar
+    pub fn get_slot_holds(env: Env, doctor: Address) -> Vec<SlotHold> {
+        active_holds(&env, &doctor)
+    }
+
+    /// Queue for the doctor's next freed slot inside `[window_start, window_end)`
+    pub fn join_waitlist(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        window_start: u64,
+        window_end: u64,
+    ) {
+        patient.require_auth();
+
+        let now = env.ledger().timestamp();
+        if window_start >= window_end || window_end <= now {
+            panic_with_error!(&env, Error::InvalidTimeRange);
+        }
+
+        let waitlist = current_waitlist(&env, &doctor);
+        for entry in waitlist.iter() {
+            if entry.patient == patient {
+                panic_with_error!(&env, Error::AlreadyOnWaitlist);
+            }
+        }
+        if waitlist.len() >= MAX_WAITLIST_SIZE {
+            panic_with_error!(&env, Error::WaitlistFull);
+        }
+
+        let mut waitlist = waitlist;
+        waitlist.push_back(WaitlistEntry {
+            patient: patient.clone(),
+            window_start,
+            window_end,
+            joined_at: now,
+        });
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Waitlist(doctor.clone()), &waitlist);
+
+        env.events().publish(
+            (symbol_short!("wl_join"), doctor),
+            (patient, window_start, window_end),
+        );
+    }
+
+    pub fn leave_waitlist(env: Env, patient: Address, doctor: Address) {
+        patient.require_auth();
+
+        if !remove_from_waitlist(&env, &doctor, &patient) {
+            panic_with_error!(&env, Error::NotOnWaitlist);
+        }
+
+        env.events()
+            .publish((symbol_short!("wl_leave"), doctor), patient);
+    }
+
+    /// Waitlisted patients whose window has not yet passed, in queue order
+    pub fn get_waitlist(env: Env, doctor: Address) -> Vec<WaitlistEntry> {
+        current_waitlist(&env, &doctor)
+    }
+
+    /// Pass lapsed waitlist offers on to the next matching patient
+    ///
+    /// Bookings and cancellations do this automatically; anyone may call it to
+    /// move offers along in between.
+    pub fn refresh_waitlist_offers(env: Env, doctor: Address) {
+        roll_over_offers(&env, &doctor);
+    }
+
+    /// Turn down a waitlist offer so it moves to the next patient straight away
+    pub fn decline_waitlist_offer(env: Env, patient: Address, doctor: Address, slot_start: u64) {
+        patient.require_auth();
+
+        roll_over_offers(&env, &doctor);
+
+        let key = AppointmentKey::Holds(doctor.clone());
+        let mut holds = active_holds(&env, &doctor);
+        let mut declined = None;
+        for (i, hold) in holds.iter().enumerate() {
+            if hold.kind == HoldKind::WaitlistOffer
+                && hold.patient == patient
+                && hold.slot.start == slot_start
+            {
+                declined = Some((i as u32, hold));
+                break;
+            }
+        }
+        let (index, hold) =
+            declined.unwrap_or_else(|| panic_with_error!(&env, Error::OfferNotFound));
+
+        holds.remove(index);
+        env.storage().persistent().set(&key, &holds);
+
+        env.events().publish(
+            (symbol_short!("wl_decl"), doctor.clone()),
+            (patient.clone(), slot_start),
+        );
+
+        let mut passed_over = hold.passed_over;
+        passed_over.push_back(patient);
+        offer_to_waitlist(
+            &env,
+            &doctor,
+            hold.slot,
+            hold.cancelled_appointment,
+            passed_over,
+        );
+    }
+
+    /// Propose moving a scheduled appointment to `new_datetime`
+    ///
+    /// Either the patient or the doctor may propose; the other party must accept.
+    /// A new proposal replaces any pending one.
+    pub fn propose_reschedule(
+        env: Env,
+        proposer: Address,
+        appointment_id: u64,
+        new_datetime: u64,
+        reason: String,
+    ) {
+        proposer.require_auth();
+
+        let appointment = load_appointment(&env, appointment_id);
+        if proposer != appointment.patient && proposer != appointment.doctor {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+        if appointment.status != AppointmentStatus::Scheduled {
+            panic_with_error!(&env, Error::InvalidAppointmentStatus);
+        }
+        if let Err(err) = check_slot(
+            &env,
+            &appointment.doctor,
+            &appointment.patient,
+            new_datetime,
+            Some(appointment_id),
+        ) {
+            panic_with_error!(&env, err);
+        }
+
+        let proposal = RescheduleProposal {
+            proposed_by: proposer.clone(),
+            new_datetime,
+            reason,
+            proposed_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(
+            &AppointmentKey::RescheduleProposal(appointment_id),
+            &proposal,
+        );
+
+        env.events().publish(
+            (symbol_short!("rsc_prop"), appointment_id),
+            (proposer, new_datetime),
+        );
+    }
+
+    /// Accept the pending proposal, moving the appointment while keeping its id
+    pub fn accept_reschedule(env: Env, acceptor: Address, appointment_id: u64) {
+        acceptor.require_auth();
+
+        let proposal_key = AppointmentKey::RescheduleProposal(appointment_id);
+        let proposal: RescheduleProposal = env
+            .storage()
+            .persistent()
+            .get(&proposal_key)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoPendingReschedule));
+
+        let mut appointment = load_appointment(&env, appointment_id);
+        let is_party = acceptor == appointment.patient || acceptor == appointment.doctor;
+        if !is_party || acceptor == proposal.proposed_by {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+        if appointment.status != AppointmentStatus::Scheduled {
+            panic_with_error!(&env, Error::InvalidAppointmentStatus);
+        }
+
+        // The slot may have been taken since the proposal was made
+        roll_over_offers(&env, &appointment.doctor);
+        let duration = match check_slot(
+            &env,
+            &appointment.doctor,
+            &appointment.patient,
+            proposal.new_datetime,
+            Some(appointment_id),
+        ) {
+            Ok(duration) => duration,
+            Err(err) => panic_with_error!(&env, err),
+        };
+        release_holds(
+            &env,
+            &appointment.doctor,
+            &appointment.patient,
+            proposal.new_datetime,
+            proposal.new_datetime + duration,
+        );
+
+        let previous_datetime = appointment.datetime;
+        remove_from_doctor_day(&env, &appointment.doctor, previous_datetime, appointment_id);
+        add_to_doctor_day(
+            &env,
+            &appointment.doctor,
+            proposal.new_datetime,
+            appointment_id,
+        );
+
+        appointment.datetime = proposal.new_datetime;
+        appointment.duration = duration;
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Appointment(appointment_id), &appointment);
+
+        let history_key = AppointmentKey::RescheduleHistory(appointment_id);
+        let mut history: Vec<RescheduleRecord> = env
+            .storage()
+            .persistent()
+            .get(&history_key)
+            .unwrap_or(Vec::new(&env));
+        history.push_back(RescheduleRecord {
+            previous_datetime,
+            new_datetime: proposal.new_datetime,
+            changed_by: proposal.proposed_by,
+            accepted_by: acceptor,
+            reason: proposal.reason,
+            changed_at: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&history_key, &history);
+        env.storage().persistent().remove(&proposal_key);
+
+        // Calendar sync event
+        env.events().publish(
+            (symbol_short!("appt_rsc"), appointment_id),
+            (
+                appointment.patient,
+                appointment.doctor,
+                previous_datetime,
+                appointment.datetime,
+            ),
+        );
+    }
+
+    /// Decline (or, for the proposer, withdraw) the pending proposal
+    pub fn decline_reschedule(env: Env, caller: Address, appointment_id: u64) {
+        caller.require_auth();
+
+        let proposal_key = AppointmentKey::RescheduleProposal(appointment_id);
+        if !env.storage().persistent().has(&proposal_key) {
+            panic_with_error!(&env, Error::NoPendingReschedule);
+        }
+
+        let appointment = load_appointment(&env, appointment_id);
+        if caller != appointment.patient && caller != appointment.doctor {
+            panic_with_error!(&env, Error::UnauthorizedAppointmentAction);
+        }
+
+        env.storage().persistent().remove(&proposal_key);
+
+        env.events()
+            .publish((symbol_short!("rsc_decl"), appointment_id), caller);
+    }
+
+    pub fn get_reschedule_proposal(env: Env, appointment_id: u64) -> Option<RescheduleProposal> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::RescheduleProposal(appointment_id))
+    }
+
+    pub fn get_reschedule_history(env: Env, appointment_id: u64) -> Vec<RescheduleRecord> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::RescheduleHistory(appointment_id))
+            .unwrap_or(Vec::new(&env))
+    }
+}
+
+fn conflict_reason(env: &Env, err: Error) -> ConflictReason {
+    match err {
+        Error::SlotUnavailable => ConflictReason::SlotUnavailable,
+        Error::SlotAlreadyBooked => ConflictReason::SlotAlreadyBooked,
+        Error::SlotHeld => ConflictReason::SlotHeld,
+        Error::NoAvailabilityPublished => ConflictReason::NoAvailabilityPublished,
+        Error::InsufficientDeposit => ConflictReason::InsufficientDeposit,
+        other => panic_with_error!(env, other),
+    }
+}
+
+/// Book a single slot for `patient`; callers handle auth and the doctor's verification
+fn book_appointment(
+    env: &Env,
+    patient: &Address,
+    doctor: &Address,
+    datetime: u64,
+    series_id: Option<u64>,
+) -> Result<u64, Error> {
+    // Reject bookings outside the doctor's published slots or on top of another booking
+    let duration = check_slot(env, doctor, patient, datetime, None)?;
+
+    // Get next appointment ID
+    let counter_key = AppointmentKey::AppointmentCounter;
+    let appointment_id = env.storage().persistent().get(&counter_key).unwrap_or(0u64) + 1;
+
+    // Escrow the doctor's booking deposit, if they require one
+    let policy: Option<DepositPolicy> = env
+        .storage()
+        .persistent()
+        .get(&AppointmentKey::DepositPolicy(doctor.clone()));
+    if let Some(policy) = policy {
+        // Checked up front so one unaffordable occurrence doesn't undo a whole series
+        let token = token::Client::new(env, &policy.token);
+        if token.balance(patient) < policy.amount {
+            return Err(Error::InsufficientDeposit);
+        }
+        token.transfer(patient, env.current_contract_address(), &policy.amount);
+        let deposit = AppointmentDeposit {
+            policy,
+            settled: false,
+        };
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Deposit(appointment_id), &deposit);
+    }
+
+    // Create appointment
+    let appointment = Appointment {
+        id: appointment_id,
+        patient: patient.clone(),
+        doctor: doctor.clone(),
+        datetime,
+        duration,
+        status: AppointmentStatus::Scheduled,
+        series_id,
+    };
+
+    // Store appointment
+    let appointment_key = AppointmentKey::Appointment(appointment_id);
+    env.storage()
+        .persistent()
+        .set(&appointment_key, &appointment);
+
+    // Update counter
+    env.storage()
+        .persistent()
+        .set(&counter_key, &appointment_id);
+
+    // Add to patient's and doctor's appointments
+    add_to_user_index(env, patient, AppointmentRole::Patient, appointment_id);
+    add_to_user_index(env, doctor, AppointmentRole::Doctor, appointment_id);
+
+    // Index by day so overlap checks only touch the doctor's bookings around `datetime`
+    add_to_doctor_day(env, doctor, datetime, appointment_id);
+    release_holds(env, doctor, patient, datetime, datetime + duration);
+
+    // Emit event
+    env.events().publish(
+        (symbol_short!("appt_cr"), appointment_id),
+        (patient.clone(), doctor.clone()),
+    );
+
+    Ok(appointment_id)
+}
+
+/// Cancel a scheduled appointment on the patient's behalf; callers check auth and status
+fn cancel_for_patient(env: &Env, mut appointment: Appointment) {
+    let appointment_id = appointment.id;
+    appointment.status = AppointmentStatus::Canceled;
+    env.storage()
+        .persistent()
+        .set(&AppointmentKey::Appointment(appointment_id), &appointment);
+
+    let record = CancellationRecord {
+        cancelled_by: appointment.patient.clone(),
+        reason: CancellationReason::PatientRequest,
+        cancelled_at: env.ledger().timestamp(),
+    };
+    env.storage()
+        .persistent()
+        .set(&AppointmentKey::Cancellation(appointment_id), &record);
+
+    settle_deposit(env, &appointment, |policy| {
+        let now = env.ledger().timestamp();
+        if now.saturating_add(policy.cancellation_notice) <= appointment.datetime {
+            0
+        } else {
+            policy.late_cancel_forfeit_bps
+        }
+    });
+
+    // Emit event
+    env.events().publish(
+        (symbol_short!("appt_can"), appointment_id),
+        appointment.patient.clone(),
+    );
+
+    // Give the freed slot to the first matching patient on the waitlist
+    roll_over_offers(env, &appointment.doctor);
+    let slot = TimeSlot {
+        start: appointment.datetime,
+        end: appointment.datetime + appointment.duration,
+    };
+    offer_to_waitlist(
+        env,
+        &appointment.doctor,
+        slot,
+        appointment_id,
+        Vec::new(env),
+    );
+}
+
+/// Whether an appointment in this status still holds its slot
+fn occupies_slot(status: &AppointmentStatus) -> bool {
+    matches!(
+        status,
+        AppointmentStatus::Scheduled | AppointmentStatus::CheckedIn | AppointmentStatus::InProgress
+    )
+}
+
+fn load_timeline(env: &Env, appointment_id: u64) -> AppointmentTimeline {
+    env.storage()
+        .persistent()
+        .get(&AppointmentKey::Timeline(appointment_id))
+        .unwrap_or(AppointmentTimeline {
+            checked_in_at: None,
+            started_at: None,
+            completed_at: None,
+        })
+}
+
+fn save_timeline(env: &Env, appointment_id: u64, timeline: &AppointmentTimeline) {
+    env.storage()
+        .persistent()
+        .set(&AppointmentKey::Timeline(appointment_id), timeline);
+}
+
+fn load_wait_metrics(env: &Env, doctor: &Address) -> WaitTimeMetrics {
+    env.storage()
+        .persistent()
+        .get(&AppointmentKey::WaitMetrics(doctor.clone()))
+        .unwrap_or(WaitTimeMetrics {
+            waits_recorded: 0,
+            total_wait: 0,
+            average_wait: 0,
+            consultations_recorded: 0,
+            total_consultation: 0,
+            average_consultation: 0,
+        })
+}
+
+fn record_wait_metrics(env: &Env, doctor: &Address, wait: Option<u64>, consultation: Option<u64>) {
+    let mut metrics = load_wait_metrics(env, doctor);
+    if let Some(wait) = wait {
+        metrics.waits_recorded += 1;
+        metrics.total_wait += wait;
+        metrics.average_wait = metrics.total_wait / u64::from(metrics.waits_recorded);
+    }
+    if let Some(consultation) = consultation {
+        metrics.consultations_recorded += 1;
+        metrics.total_consultation += consultation;
+        metrics.average_consultation =
+            metrics.total_consultation / u64::from(metrics.consultations_recorded);
+    }
+    env.storage()
+        .persistent()
+        .set(&AppointmentKey::WaitMetrics(doctor.clone()), &metrics);
+}
+
+fn load_appointment(env: &Env, appointment_id: u64) -> Appointment {
+    find_appointment(env, appointment_id)
+        .unwrap_or_else(|| panic_with_error!(env, Error::AppointmentNotFound))
+}
+
+/// The appointment with `appointment_id`, upgrading one stored in the legacy layout
+fn find_appointment(env: &Env, appointment_id: u64) -> Option<Appointment> {
+    let key = AppointmentKey::Appointment(appointment_id);
+    // Decoding a struct with the wrong fields traps, so look at the field names first
+    let fields: Map<Symbol, Val> = env.storage().persistent().get(&key)?;
+    if fields.contains_key(Symbol::new(env, "duration")) {
+        return env.storage().persistent().get(&key);
+    }
+
+    let legacy: LegacyAppointment = env.storage().persistent().get(&key)?;
+    Some(Appointment {
+        id: legacy.id,
+        patient: legacy.patient,
+        doctor: legacy.doctor,
+        datetime: legacy.datetime,
+        duration: LEGACY_APPOINTMENT_DURATION,
+        status: legacy.status,
+        series_id: None,
+    })
+}
+
+/// Release an unsettled deposit, paying the doctor `forfeit_bps` of it and refunding the rest
+fn settle_deposit<F>(env: &Env, appointment: &Appointment, forfeit_bps: F)
+where
+    F: FnOnce(&DepositPolicy) -> u32,
+{
+    let key = AppointmentKey::Deposit(appointment.id);
+    let mut deposit: AppointmentDeposit = match env.storage().persistent().get(&key) {
+        Some(deposit) => deposit,
+        None => return,
+    };
+    if deposit.settled {
+        return;
+    }
+
+    let policy = &deposit.policy;
+    let forfeited = policy.amount * i128::from(forfeit_bps(policy)) / i128::from(BPS_DENOMINATOR);
+    let refunded = policy.amount - forfeited;
+
+    let client = token::Client::new(env, &policy.token);
+    let contract = env.current_contract_address();
+    if forfeited > 0 {
+        client.transfer(&contract, &appointment.doctor, &forfeited);
+    }
+    if refunded > 0 {
+        client.transfer(&contract, &appointment.patient, &refunded);
+    }
+
+    deposit.settled = true;
+    env.storage().persistent().set(&key, &deposit);
+
+    env.events().publish(
+        (symbol_short!("dep_setl"), appointment.id),
+        (refunded, forfeited),
+    );
+}
+
+fn load_config(env: &Env) -> SchedulingConfig {
+    env.storage()
+        .instance()
+        .get(&AppointmentKey::Config)
+        .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+}
+
+/// Whether `caller` is the verified institution the doctor-registry lists for `doctor`
+fn is_doctor_institution(env: &Env, doctor: &Address, caller: &Address) -> bool {
+    let config = load_config(env);
+
+    let profile = match DoctorRegistryClient::new(env, &config.doctor_registry)
+        .try_get_doctor_profile(doctor)
+    {
+        Ok(Ok(profile)) => profile,
+        _ => return false,
+    };
+    if profile.institution_wallet != *caller {
+        return false;
+    }
+
+    // A suspended, revoked or lapsed institution no longer acts for its doctors
+    matches!(
+        HealthcareRegistryClient::new(env, &config.healthcare_registry).try_get_institution(caller),
+        Ok(Ok(institution)) if institution.is_verified
+    )
+}
+
+/// Ensure `doctor` has a doctor-registry profile whose institution is verified
+fn check_doctor_verified(env: &Env, doctor: &Address) -> Result<(), Error> {
+    let config: SchedulingConfig = env
+        .storage()
+        .instance()
+        .get(&AppointmentKey::Config)
+        .ok_or(Error::NotInitialized)?;
+
+    let profile = match DoctorRegistryClient::new(env, &config.doctor_registry)
+        .try_get_doctor_profile(doctor)
+    {
+        Ok(Ok(profile)) => profile,
+        _ => return Err(Error::DoctorNotRegistered),
+    };
+
+    match HealthcareRegistryClient::new(env, &config.healthcare_registry)
+        .try_get_institution(&profile.institution_wallet)
+    {
+        Ok(Ok(institution)) if institution.is_verified => Ok(()),
+        _ => Err(Error::InstitutionNotVerified),
+    }
+}
+
+fn load_availability(env: &Env, doctor: &Address) -> DoctorAvailability {
+    env.storage()
+        .persistent()
+        .get(&AppointmentKey::Availability(doctor.clone()))
+        .unwrap_or_else(|| panic_with_error!(env, Error::NoAvailabilityPublished))
+}
+
+/// 0 = Monday ... 6 = Sunday; the Unix epoch fell on a Thursday
+fn day_of_week(day: u64) -> u32 {
+    ((day + 3) % 7) as u32
+}
+
+/// All published slots starting on `day`, sorted by start time
+fn day_slots(env: &Env, availability: &DoctorAvailability, day: u64) -> Vec<TimeSlot> {
+    let duration = availability.slot_duration;
+    let day_start = day * DAY_SECONDS;
+    let day_end = day_start + DAY_SECONDS;
+    let mut slots: Vec<TimeSlot> = Vec::new(env);
+
+    for window in availability.weekly_windows.iter() {
+        if window.day_of_week != day_of_week(day) {
+            continue;
+        }
+        let mut start = day_start + u64::from(window.start_time);
+        while start + duration <= day_start + u64::from(window.end_time) {
+            insert_slot(&mut slots, start, duration);
+            start += duration;
+        }
+    }
+
+    for exception in availability.exceptions.iter() {
+        if !exception.available || exception.end <= day_start || exception.start >= day_end {
+            continue;
+        }
+        // Step along the exception's own grid until we reach this day
+        let mut start = exception.start;
+        if start < day_start {
+            start += (day_start - start).div_ceil(duration) * duration;
+        }
+        while start < day_end && start + duration <= exception.end {
+            insert_slot(&mut slots, start, duration);
+            start += duration;
+        }
+    }
+
+    slots
+}
+
+/// Whether `start` lies on the slot grid of a weekly window or an open exception
+fn is_published(availability: &DoctorAvailability, start: u64) -> bool {
+    let duration = availability.slot_duration;
+    let day = start / DAY_SECONDS;
+    let day_start = day * DAY_SECONDS;
+
+    let in_weekly_window = availability.weekly_windows.iter().any(|window| {
+        let window_start = day_start + u64::from(window.start_time);
+        window.day_of_week == day_of_week(day)
+            && start >= window_start
+            && start + duration <= day_start + u64::from(window.end_time)
+            && (start - window_start).is_multiple_of(duration)
+    });
+    in_weekly_window
+        || availability.exceptions.iter().any(|exception| {
+            exception.available
+                && start >= exception.start
+                && start + duration <= exception.end
+                && (start - exception.start).is_multiple_of(duration)
+        })
+}
+
+fn insert_slot(slots: &mut Vec<TimeSlot>, start: u64, duration: u64) {
+    let mut index = slots.len();
+    for (i, slot) in slots.iter().enumerate() {
+        if slot.start == start {
+            return;
+        }
+        if slot.start > start {
+            index = i as u32;
+            break;
+        }
+    }
+    slots.insert(
+        index,
+        TimeSlot {
+            start,
+            end: start + duration,
+        },
+    );
+}
+
+fn is_blocked(availability: &DoctorAvailability, start: u64, end: u64) -> bool {
+    for exception in availability.exceptions.iter() {
+        if !exception.available && exception.start < end && start < exception.end {
+            return true;
+        }
+    }
+    false
+}
+
+/// Return the first `Scheduled` appointment of `doctor` overlapping `[start, end)`
+fn find_overlap(
+    env: &Env,
+    doctor: &Address,
+    start: u64,
+    end: u64,
+    exclude: Option<u64>,
+) -> Option<u64> {
+    // Slots never exceed a day, so an overlapping booking starts at most a day before `start`,
+    // and the range itself may run past midnight
+    let first_day = (start / DAY_SECONDS).saturating_sub(1);
+    let last_day = end.saturating_sub(1) / DAY_SECONDS;
+    for d in first_day..=last_day {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::DoctorDay(doctor.clone(), d))
+            .unwrap_or(Vec::new(env));
+        for id in ids.iter() {
+            if Some(id) == exclude {
+                continue;
+            }
+            let appointment: Appointment = match find_appointment(env, id) {
+                Some(appointment) => appointment,
+                None => continue,
+            };
+            if occupies_slot(&appointment.status)
+                && appointment.datetime < end
+                && start < appointment.datetime + appointment.duration
+            {
+                return Some(id);
+            }
+        }
+    }
+    None
+}
+
+/// Holds on the doctor's calendar that have not yet expired
+fn active_holds(env: &Env, doctor: &Address) -> Vec<SlotHold> {
+    let holds: Vec<SlotHold> = env
+        .storage()
+        .persistent()
+        .get(&AppointmentKey::Holds(doctor.clone()))
+        .unwrap_or(Vec::new(env));
+
+    let now = env.ledger().timestamp();
+    let mut active = Vec::new(env);
+    for hold in holds.iter() {
+        if hold.expires_at > now {
+            active.push_back(hold);
+        }
+    }
+    active
+}
+
+/// The patient holding a slot overlapping `[start, end)`, if any
+fn hold_owner(holds: &Vec<SlotHold>, start: u64, end: u64) -> Option<Address> {
+    for hold in holds.iter() {
+        if hold.slot.start < end && start < hold.slot.end {
+            return Some(hold.patient);
+        }
+    }
+    None
+}
+
+/// Drop the patient's holds overlapping a slot they have just booked
+///
+/// Claiming a waitlist offer also takes the patient off the doctor's waitlist.
+fn release_holds(env: &Env, doctor: &Address, patient: &Address, start: u64, end: u64) {
+    let key = AppointmentKey::Holds(doctor.clone());
+    if !env.storage().persistent().has(&key) {
+        return;
+    }
+
+    let holds = active_holds(env, doctor);
+    let mut remaining = Vec::new(env);
+    let mut claimed_offer = false;
+    for hold in holds.iter() {
+        let overlaps = hold.slot.start < end && start < hold.slot.end;
+        if overlaps && hold.patient == *patient {
+            claimed_offer |= hold.kind == HoldKind::WaitlistOffer;
+        } else {
+            remaining.push_back(hold);
+        }
+    }
+    env.storage().persistent().set(&key, &remaining);
+
+    if claimed_offer {
+        remove_from_waitlist(env, doctor, patient);
+    }
+}
+
+/// The doctor's waitlist without entries whose window has passed
+fn current_waitlist(env: &Env, doctor: &Address) -> Vec<WaitlistEntry> {
+    let waitlist: Vec<WaitlistEntry> = env
+        .storage()
+        .persistent()
+        .get(&AppointmentKey::Waitlist(doctor.clone()))
+        .unwrap_or(Vec::new(env));
+
+    let now = env.ledger().timestamp();
+    let mut current = Vec::new(env);
+    for entry in waitlist.iter() {
+        if entry.window_end > now {
+            current.push_back(entry);
+        }
+    }
+    current
+}
+
+fn remove_from_waitlist(env: &Env, doctor: &Address, patient: &Address) -> bool {
+    let waitlist = current_waitlist(env, doctor);
+    let mut remaining = Vec::new(env);
+    for entry in waitlist.iter() {
+        if entry.patient != *patient {
+            remaining.push_back(entry);
+        }
+    }
+    if remaining.len() == waitlist.len() {
+        return false;
+    }
+
+    env.storage()
+        .persistent()
+        .set(&AppointmentKey::Waitlist(doctor.clone()), &remaining);
+    true
+}
+
+/// Hold `slot` for the first waitlisted patient whose window covers it
+fn offer_to_waitlist(
+    env: &Env,
+    doctor: &Address,
+    slot: TimeSlot,
+    cancelled_appointment: u64,
+    passed_over: Vec<Address>,
+) {
+    let now = env.ledger().timestamp();
+    if slot.start <= now {
+        return;
+    }
+
+    // The doctor may have blocked the time off since it was freed
+    let availability: DoctorAvailability = match env
+        .storage()
+        .persistent()
+        .get(&AppointmentKey::Availability(doctor.clone()))
+    {
+        Some(availability) => availability,
+        None => return,
+    };
+    let holds = active_holds(env, doctor);
+    if !is_slot_free(env, doctor, &availability, &holds, &slot) {
+        return;
+    }
+
+    let mut candidate = None;
+    for entry in current_waitlist(env, doctor).iter() {
+        if entry.window_start <= slot.start
+            && slot.end <= entry.window_end
+            && !passed_over.contains(&entry.patient)
+        {
+            candidate = Some(entry.patient);
+            break;
+        }
+    }
+    let patient = match candidate {
+        Some(patient) => patient,
+        None => return,
+    };
+
+    let hold = SlotHold {
+        patient: patient.clone(),
+        slot: slot.clone(),
+        expires_at: core::cmp::min(slot.start, now + WAITLIST_CLAIM_PERIOD),
+        kind: HoldKind::WaitlistOffer,
+        cancelled_appointment,
+        passed_over,
+    };
+    let mut holds = holds;
+    holds.push_back(hold.clone());
+    env.storage()
+        .persistent()
+        .set(&AppointmentKey::Holds(doctor.clone()), &holds);
+
+    env.events().publish(
+        (symbol_short!("wl_offer"), doctor.clone()),
+        (patient, slot.start, hold.expires_at),
+    );
+}
+
+/// Drop expired holds, passing lapsed waitlist offers to the next patient in line
+fn roll_over_offers(env: &Env, doctor: &Address) {
+    let key = AppointmentKey::Holds(doctor.clone());
+    let holds: Vec<SlotHold> = match env.storage().persistent().get(&key) {
+        Some(holds) => holds,
+        None => return,
+    };
+
+    let now = env.ledger().timestamp();
+    let mut active = Vec::new(env);
+    let mut lapsed = Vec::new(env);
+    for hold in holds.iter() {
+        if hold.expires_at > now {
+            active.push_back(hold);
+        } else if hold.kind == HoldKind::WaitlistOffer {
+            lapsed.push_back(hold);
+        }
+    }
+    if active.len() == holds.len() {
+        return;
+    }
+    env.storage().persistent().set(&key, &active);
+
+    for hold in lapsed.iter() {
+        let mut passed_over = hold.passed_over;
+        passed_over.push_back(hold.patient);
+        offer_to_waitlist(
+            env,
+            doctor,
+            hold.slot,
+            hold.cancelled_appointment,
+            passed_over,
+        );
+    }
+}
+
+fn is_slot_free(
+    env: &Env,
+    doctor: &Address,
+    availability: &DoctorAvailability,
+    holds: &Vec<SlotHold>,
+    slot: &TimeSlot,
+) -> bool {
+    !is_blocked(availability, slot.start, slot.end)
+        && hold_owner(holds, slot.start, slot.end).is_none()
+        && find_overlap(env, doctor, slot.start, slot.end, None).is_none()
+}
+
+/// The doctor's first free slot starting at or after `after`, within the rebooking range
+fn next_free_slot(env: &Env, doctor: &Address, after: u64) -> Option<TimeSlot> {
+    let availability: DoctorAvailability = env
+        .storage()
+        .persistent()
+        .get(&AppointmentKey::Availability(doctor.clone()))?;
+    let holds = active_holds(env, doctor);
+
+    let last_day = (after + REBOOKING_SEARCH_RANGE) / DAY_SECONDS;
+    for day in (after / DAY_SECONDS)..=last_day {
+        for slot in day_slots(env, &availability, day).iter() {
+            if slot.start >= after && is_slot_free(env, doctor, &availability, &holds, &slot) {
+                return Some(slot);
+            }
+        }
+    }
+    None
+}
+
+/// Validate that `start` is a free published slot of `doctor` and return the slot length
+fn check_slot(
+    env: &Env,
+    doctor: &Address,
+    patient: &Address,
+    start: u64,
+    exclude: Option<u64>,
+) -> Result<u64, Error> {
+    let availability: DoctorAvailability = env
+        .storage()
+        .persistent()
+        .get(&AppointmentKey::Availability(doctor.clone()))
+        .ok_or(Error::NoAvailabilityPublished)?;
+    let duration = availability.slot_duration;
+
+    if start < env.ledger().timestamp() {
+        return Err(Error::SlotUnavailable);
+    }
+
+    if !is_published(&availability, start) || is_blocked(&availability, start, start + duration) {
+        return Err(Error::SlotUnavailable);
+    }
+
+    if find_overlap(env, doctor, start, start + duration, exclude).is_some() {
+        return Err(Error::SlotAlreadyBooked);
+    }
+
+    if let Some(holder) = hold_owner(&active_holds(env, doctor), start, start + duration) {
+        if holder != *patient {
+            return Err(Error::SlotHeld);
+        }
+    }
+
+    Ok(duration)
+}
+
+fn user_index_len(env: &Env, user: &Address, role: AppointmentRole) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&AppointmentKey::RoleAppointmentCount(user.clone(), role))
+        .unwrap_or(0)
+}
+
+fn add_to_user_index(env: &Env, user: &Address, role: AppointmentRole, appointment_id: u64) {
+    let position = user_index_len(env, user, role);
+    env.storage().persistent().set(
+        &AppointmentKey::RoleAppointment(user.clone(), role, position),
+        &appointment_id,
+    );
+    env.storage().persistent().set(
+        &AppointmentKey::RoleAppointmentCount(user.clone(), role),
+        &(position + 1),
+    );
+}
+
+fn matches_filter(appointment: &Appointment, filter: &AppointmentFilter) -> bool {
+    if !filter.statuses.is_empty() && !filter.statuses.contains(&appointment.status) {
+        return false;
+    }
+    if let Some(from) = filter.from {
+        if appointment.datetime < from {
+            return false;
+        }
+    }
+    if let Some(to) = filter.to {
+        if appointment.datetime >= to {
+            return false;
+        }
+    }
+    true
+}
+
+fn add_to_doctor_day(env: &Env, doctor: &Address, datetime: u64, appointment_id: u64) {
+    let key = AppointmentKey::DoctorDay(doctor.clone(), datetime / DAY_SECONDS);
+    let mut ids: Vec<u64> = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    ids.push_back(appointment_id);
+    env.storage().persistent().set(&key, &ids);
+}
+
+fn remove_from_doctor_day(env: &Env, doctor: &Address, datetime: u64, appointment_id: u64) {
+    let key = AppointmentKey::DoctorDay(doctor.clone(), datetime / DAY_SECONDS);
+    let ids: Vec<u64> = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .unwrap_or(Vec::new(env));
+    if let Some(index) = ids.first_index_of(appointment_id) {
+        let mut ids = ids;
+        ids.remove(index);
+        env.storage().persistent().set(&key, &ids);
     }
 }
 
@@ -1,11 +1,19 @@
 #[cfg(test)]
 mod test {
     use crate::{
-        AppointmentScheduling, AppointmentSchedulingClient, AppointmentStatus, HealthcareRegistry,
-        HealthcareRegistryClient,
+        Appointment, AppointmentFilter, AppointmentKey, AppointmentRole, AppointmentScheduling,
+        AppointmentSchedulingClient, AppointmentStatus, AvailabilityException, CancellationReason,
+        ConflictReason, DepositPolicy, Error, HealthcareRegistry, HealthcareRegistryClient,
+        HoldKind, InstitutionStatus, LegacyAppointment, RecurrenceRule, SeriesConflict, TimeSlot,
+        WeeklyWindow,
     };
 
-    use soroban_sdk::{testutils::Address as _, Address, Env, String, Vec};
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger},
+        token, vec, Address, BytesN, Env, String, Vec,
+    };
+
+    const SLOT: u64 = 1800; // 30 minutes
 
     fn setup_test(env: &Env) -> (HealthcareRegistryClient<'static>, Address, Address) {
         // Updated from register_contract to register
@@ -15,6 +23,7 @@ mod test {
         let admin = Address::generate(env);
         let institution = Address::generate(env);
 
+        env.mock_all_auths();
         client.init(&admin);
 
         (client, admin, institution)
@@ -29,9 +38,67 @@ mod test {
         let patient = Address::generate(env);
         let doctor = Address::generate(env);
 
+        // Doctor is bookable 00:00-04:00 UTC every day
+        let mut windows = Vec::new(env);
+        for day in 0..7 {
+            windows.push_back(WeeklyWindow {
+                day_of_week: day,
+                start_time: 0,
+                end_time: 4 * 3600,
+            });
+        }
+        env.mock_all_auths();
+        client.set_availability(&doctor, &SLOT, &windows);
+
+        setup_registries(env, &client, &doctor);
+
         (client, patient, doctor)
     }
 
+    /// Register both registries, a verified institution and the doctor's profile
+    fn setup_registries(env: &Env, client: &AppointmentSchedulingClient, doctor: &Address) {
+        let admin = Address::generate(env);
+        let institution = Address::generate(env);
+
+        let registry_id = env.register(HealthcareRegistry, ());
+        let registry = HealthcareRegistryClient::new(env, &registry_id);
+        registry.init(&admin);
+        let name = String::from_str(env, "General Hospital");
+        registry.register_institution(&institution, &name, &name, &name);
+        registry.verify_institution(&admin, &institution);
+
+        let doctor_registry_id = env.register(doctor_registry::DoctorRegistry, ());
+        client.initialize(&admin, &registry_id, &doctor_registry_id);
+
+        register_doctor(env, client, doctor, &institution);
+    }
+
+    fn register_doctor(
+        env: &Env,
+        client: &AppointmentSchedulingClient,
+        doctor: &Address,
+        institution: &Address,
+    ) {
+        let doctor_registry = client.get_config().doctor_registry;
+        doctor_registry::DoctorRegistryClient::new(env, &doctor_registry).create_doctor_profile(
+            doctor,
+            &String::from_str(env, "Dr. Jane Smith"),
+            &String::from_str(env, "Cardiology"),
+            institution,
+        );
+    }
+
+    fn doctor_institution(
+        env: &Env,
+        client: &AppointmentSchedulingClient,
+        doctor: &Address,
+    ) -> Address {
+        let doctor_registry = client.get_config().doctor_registry;
+        doctor_registry::DoctorRegistryClient::new(env, &doctor_registry)
+            .get_doctor_profile(doctor)
+            .institution_wallet
+    }
+
     #[test]
     fn test_register_and_get() {
         let env = Env::default();
@@ -145,6 +212,10 @@ mod test {
         let patient_appointments = client.get_appointments(&patient);
         let appointment = &patient_appointments.get(0).unwrap();
         assert!(matches!(appointment.status, AppointmentStatus::Canceled));
+
+        let record = client.get_cancellation(&appointment_id).unwrap();
+        assert_eq!(record.cancelled_by, patient);
+        assert_eq!(record.reason, CancellationReason::PatientRequest);
     }
 
     #[test]
@@ -221,7 +292,7 @@ mod test {
 
         // Create appointment for patient2 with doctor
         env.mock_all_auths();
-        let appointment_id3 = client.create_appointment(&patient2, &doctor, &datetime1);
+        let appointment_id3 = client.create_appointment(&patient2, &doctor, &(datetime1 + SLOT));
 
         // Check patient's appointments
         let patient_appointments = client.get_appointments(&patient);
@@ -282,6 +353,9 @@ mod test {
                 AppointmentStatus::Scheduled => scheduled_count += 1,
                 AppointmentStatus::Canceled => canceled_count += 1,
                 AppointmentStatus::Completed => completed_count += 1,
+                AppointmentStatus::NoShow
+                | AppointmentStatus::CheckedIn
+                | AppointmentStatus::InProgress => {}
             }
         }
 
@@ -289,4 +363,1581 @@ mod test {
         assert_eq!(canceled_count, 1); // id2
         assert_eq!(completed_count, 1); // id1
     }
+
+    fn setup_deposit(
+        env: &Env,
+        client: &AppointmentSchedulingClient,
+        doctor: &Address,
+        patient: &Address,
+    ) -> token::Client<'static> {
+        let token_admin = Address::generate(env);
+        let sac = env.register_stellar_asset_contract_v2(token_admin);
+        token::StellarAssetClient::new(env, &sac.address()).mint(patient, &1000);
+
+        client.set_deposit_policy(
+            doctor,
+            &DepositPolicy {
+                token: sac.address(),
+                amount: 100,
+                cancellation_notice: 86400,
+                late_cancel_forfeit_bps: 5000,
+                no_show_forfeit_bps: 10000,
+            },
+        );
+
+        token::Client::new(env, &sac.address())
+    }
+
+    // Availability Tests
+    #[test]
+    fn test_double_booking_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        client.create_appointment(&patient, &doctor, &datetime);
+
+        let result = client.try_create_appointment(&patient2, &doctor, &datetime);
+        assert_eq!(result, Err(Ok(Error::SlotAlreadyBooked.into())));
+    }
+
+    #[test]
+    fn test_canceled_slot_can_be_rebooked() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.cancel_appointment(&patient, &id);
+
+        let id2 = client.create_appointment(&patient2, &doctor, &datetime);
+        assert_eq!(id2, 2);
+    }
+
+    #[test]
+    fn test_booking_outside_published_slots_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        // 05:00 is outside the 00:00-04:00 window
+        let result = client.try_create_appointment(&patient, &doctor, &(1640995200 + 5 * 3600));
+        assert_eq!(result, Err(Ok(Error::SlotUnavailable.into())));
+
+        // Not aligned to the 30 minute grid
+        let result = client.try_create_appointment(&patient, &doctor, &(1640995200 + 600));
+        assert_eq!(result, Err(Ok(Error::SlotUnavailable.into())));
+    }
+
+    #[test]
+    fn test_slot_duration_bounds() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let windows = client.get_availability(&doctor).weekly_windows;
+        let result = client.try_set_availability(&doctor, &60, &windows);
+        assert_eq!(result, Err(Ok(Error::InvalidAvailability.into())));
+        let result = client.try_set_availability(&doctor, &(2 * 86_400), &windows);
+        assert_eq!(result, Err(Ok(Error::InvalidAvailability.into())));
+
+        // The grid restarts at each window and exception, not at midnight
+        client.set_availability(&doctor, &(45 * 60), &windows);
+        client.add_availability_exception(
+            &doctor,
+            &AvailabilityException {
+                start: 1640995200 + 18 * 3600 + 600,
+                end: 1640995200 + 20 * 3600,
+                available: true,
+            },
+        );
+        let result = client.try_create_appointment(&patient, &doctor, &(1640995200 + 3600));
+        assert_eq!(result, Err(Ok(Error::SlotUnavailable.into())));
+        client.create_appointment(&patient, &doctor, &(1640995200 + 45 * 60));
+        client.create_appointment(&patient, &doctor, &(1640995200 + 18 * 3600 + 600 + 45 * 60));
+        let result = client.try_create_appointment(
+            &patient,
+            &doctor,
+            &(1640995200 + 18 * 3600 + 600 + 90 * 60),
+        );
+        assert_eq!(result, Err(Ok(Error::SlotUnavailable.into())));
+    }
+
+    #[test]
+    fn test_booking_without_availability_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let other_doctor = Address::generate(&env);
+        env.mock_all_auths();
+        let institution = doctor_institution(&env, &client, &doctor);
+        register_doctor(&env, &client, &other_doctor, &institution);
+
+        let result = client.try_create_appointment(&patient, &other_doctor, &1640995200);
+        assert_eq!(result, Err(Ok(Error::NoAvailabilityPublished.into())));
+    }
+
+    #[test]
+    fn test_booking_in_the_past_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        env.ledger().set_timestamp(1640995200 + 3600);
+
+        let result = client.try_create_appointment(&patient, &doctor, &1640995200);
+        assert_eq!(result, Err(Ok(Error::SlotUnavailable.into())));
+    }
+
+    #[test]
+    fn test_availability_exceptions() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+
+        // Block the first hour off
+        client.add_availability_exception(
+            &doctor,
+            &AvailabilityException {
+                start: day,
+                end: day + 3600,
+                available: false,
+            },
+        );
+        let result = client.try_create_appointment(&patient, &doctor, &day);
+        assert_eq!(result, Err(Ok(Error::SlotUnavailable.into())));
+
+        // Open an extra evening session
+        client.add_availability_exception(
+            &doctor,
+            &AvailabilityException {
+                start: day + 18 * 3600,
+                end: day + 19 * 3600,
+                available: true,
+            },
+        );
+        client.create_appointment(&patient, &doctor, &(day + 18 * 3600));
+
+        // Removing the block makes the morning bookable again
+        client.remove_availability_exception(&doctor, &day);
+        client.create_appointment(&patient, &doctor, &day);
+
+        assert_eq!(client.get_availability(&doctor).exceptions.len(), 1);
+    }
+
+    #[test]
+    fn test_overlap_detected_across_midnight() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let midnight = 1640995200 + 86400;
+        client.create_appointment(&patient, &doctor, &midnight);
+
+        // A late session whose only slot runs 23:45-00:15 collides with the 00:00 booking
+        client.add_availability_exception(
+            &doctor,
+            &AvailabilityException {
+                start: midnight - 900,
+                end: midnight + 900,
+                available: true,
+            },
+        );
+        let result = client.try_create_appointment(&patient, &doctor, &(midnight - 900));
+        assert_eq!(result, Err(Ok(Error::SlotAlreadyBooked.into())));
+    }
+
+    #[test]
+    fn test_get_free_slots() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        client.create_appointment(&patient, &doctor, &(day + SLOT));
+        client.add_availability_exception(
+            &doctor,
+            &AvailabilityException {
+                start: day + 3 * SLOT,
+                end: day + 4 * SLOT,
+                available: false,
+            },
+        );
+
+        let free = client.get_free_slots(&doctor, &day, &(day + 5 * SLOT));
+        assert_eq!(
+            free,
+            vec![
+                &env,
+                TimeSlot {
+                    start: day,
+                    end: day + SLOT,
+                },
+                TimeSlot {
+                    start: day + 2 * SLOT,
+                    end: day + 3 * SLOT,
+                },
+                TimeSlot {
+                    start: day + 4 * SLOT,
+                    end: day + 5 * SLOT,
+                },
+            ]
+        );
+
+        // Full day: 8 slots in the window, one booked and one blocked
+        let free = client.get_free_slots(&doctor, &day, &(day + 86400));
+        assert_eq!(free.len(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #8)")]
+    fn test_invalid_weekly_window_rejected() {
+        let env = Env::default();
+        let (client, _, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let windows = vec![
+            &env,
+            WeeklyWindow {
+                day_of_week: 7,
+                start_time: 0,
+                end_time: 3600,
+            },
+        ];
+        client.set_availability(&doctor, &SLOT, &windows);
+    }
+
+    // Rescheduling Tests
+    #[test]
+    fn test_reschedule_appointment() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let new_datetime = datetime + 2 * SLOT;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+
+        let reason = String::from_str(&env, "Work conflict");
+        client.propose_reschedule(&patient, &id, &new_datetime, &reason);
+        let proposal = client.get_reschedule_proposal(&id).unwrap();
+        assert_eq!(proposal.proposed_by, patient);
+        assert_eq!(proposal.new_datetime, new_datetime);
+
+        client.accept_reschedule(&doctor, &id);
+
+        let appointment = client.get_appointments(&patient).get(0).unwrap();
+        assert_eq!(appointment.id, id);
+        assert_eq!(appointment.datetime, new_datetime);
+        assert_eq!(client.get_reschedule_proposal(&id), None);
+
+        let history = client.get_reschedule_history(&id);
+        assert_eq!(history.len(), 1);
+        let record = history.get(0).unwrap();
+        assert_eq!(record.previous_datetime, datetime);
+        assert_eq!(record.new_datetime, new_datetime);
+        assert_eq!(record.changed_by, patient);
+        assert_eq!(record.accepted_by, doctor);
+        assert_eq!(record.reason, reason);
+
+        // The original slot is free again, the new one is taken
+        let free = client.get_free_slots(&doctor, &datetime, &(datetime + 3 * SLOT));
+        assert_eq!(free.len(), 2);
+        assert_eq!(free.get(0).unwrap().start, datetime);
+
+        // A second move is appended to the history
+        client.propose_reschedule(
+            &doctor,
+            &id,
+            &datetime,
+            &String::from_str(&env, "Clinic closing early"),
+        );
+        client.accept_reschedule(&patient, &id);
+        assert_eq!(client.get_reschedule_history(&id).len(), 2);
+    }
+
+    #[test]
+    fn test_proposer_cannot_accept_own_reschedule() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.propose_reschedule(
+            &patient,
+            &id,
+            &(datetime + SLOT),
+            &String::from_str(&env, "Later please"),
+        );
+
+        let result = client.try_accept_reschedule(&patient, &id);
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+    }
+
+    #[test]
+    fn test_reschedule_to_booked_slot_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.create_appointment(&patient2, &doctor, &(datetime + SLOT));
+
+        let result = client.try_propose_reschedule(
+            &patient,
+            &id,
+            &(datetime + SLOT),
+            &String::from_str(&env, "Later please"),
+        );
+        assert_eq!(result, Err(Ok(Error::SlotAlreadyBooked.into())));
+    }
+
+    #[test]
+    fn test_decline_reschedule() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.propose_reschedule(
+            &doctor,
+            &id,
+            &(datetime + SLOT),
+            &String::from_str(&env, "Running late"),
+        );
+        client.decline_reschedule(&patient, &id);
+
+        assert_eq!(client.get_reschedule_proposal(&id), None);
+        let result = client.try_accept_reschedule(&patient, &id);
+        assert_eq!(result, Err(Ok(Error::NoPendingReschedule.into())));
+
+        let appointment = client.get_appointments(&patient).get(0).unwrap();
+        assert_eq!(appointment.datetime, datetime);
+        assert_eq!(client.get_reschedule_history(&id).len(), 0);
+    }
+
+    // No-show and Deposit Tests
+    #[test]
+    fn test_deposit_refunded_on_completion() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let token = setup_deposit(&env, &client, &doctor, &patient);
+
+        let id = client.create_appointment(&patient, &doctor, &1640995200);
+        assert_eq!(token.balance(&patient), 900);
+        assert_eq!(token.balance(&client.address), 100);
+
+        client.complete_appointment(&doctor, &id);
+        assert_eq!(token.balance(&patient), 1000);
+        assert_eq!(token.balance(&client.address), 0);
+        assert!(client.get_deposit(&id).unwrap().settled);
+    }
+
+    #[test]
+    fn test_deposit_on_cancellation() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let token = setup_deposit(&env, &client, &doctor, &patient);
+
+        let datetime = 1640995200;
+
+        // Cancelled two days ahead: full refund
+        env.ledger().set_timestamp(datetime - 2 * 86400);
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.cancel_appointment(&patient, &id);
+        assert_eq!(token.balance(&patient), 1000);
+        assert_eq!(token.balance(&doctor), 0);
+
+        // Cancelled an hour ahead: half goes to the doctor
+        env.ledger().set_timestamp(datetime - 3600);
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.cancel_appointment(&patient, &id);
+        assert_eq!(token.balance(&patient), 950);
+        assert_eq!(token.balance(&doctor), 50);
+    }
+
+    #[test]
+    fn test_no_show_forfeits_deposit() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let token = setup_deposit(&env, &client, &doctor, &patient);
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+
+        // Cannot mark a no-show before the slot has ended
+        env.ledger().set_timestamp(datetime + 60);
+        let result = client.try_mark_no_show(&doctor, &id);
+        assert_eq!(result, Err(Ok(Error::AppointmentNotOver.into())));
+
+        env.ledger().set_timestamp(datetime + SLOT);
+        client.mark_no_show(&doctor, &id);
+
+        let appointment = client.get_appointments(&patient).get(0).unwrap();
+        assert_eq!(appointment.status, AppointmentStatus::NoShow);
+        assert_eq!(token.balance(&patient), 900);
+        assert_eq!(token.balance(&doctor), 100);
+    }
+
+    #[test]
+    fn test_no_show_without_deposit() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        assert_eq!(client.get_deposit(&id), None);
+
+        env.ledger().set_timestamp(datetime + SLOT);
+        let result = client.try_mark_no_show(&patient, &id);
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+
+        client.mark_no_show(&doctor, &id);
+        let appointment = client.get_appointments(&patient).get(0).unwrap();
+        assert_eq!(appointment.status, AppointmentStatus::NoShow);
+    }
+
+    #[test]
+    fn test_invalid_deposit_policy_rejected() {
+        let env = Env::default();
+        let (client, _, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let policy = DepositPolicy {
+            token: Address::generate(&env),
+            amount: 100,
+            cancellation_notice: 0,
+            late_cancel_forfeit_bps: 10001,
+            no_show_forfeit_bps: 0,
+        };
+        let result = client.try_set_deposit_policy(&doctor, &policy);
+        assert_eq!(result, Err(Ok(Error::InvalidDepositPolicy.into())));
+    }
+
+    // Provider Cancellation Tests
+    #[test]
+    fn test_doctor_cancellation_gives_rebooking_hold() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.create_appointment(&patient2, &doctor, &(datetime + SLOT));
+
+        client.cancel_appointment_by_provider(&doctor, &id, &CancellationReason::ProviderIllness);
+
+        let appointment = client.get_appointments(&patient).get(0).unwrap();
+        assert_eq!(appointment.status, AppointmentStatus::Canceled);
+        let record = client.get_cancellation(&id).unwrap();
+        assert_eq!(record.cancelled_by, doctor);
+        assert_eq!(record.reason, CancellationReason::ProviderIllness);
+
+        // Next free slot after the cancelled one skips patient2's booking
+        let holds = client.get_slot_holds(&doctor);
+        assert_eq!(holds.len(), 1);
+        let hold = holds.get(0).unwrap();
+        assert_eq!(hold.patient, patient);
+        assert_eq!(hold.slot.start, datetime + 2 * SLOT);
+        assert_eq!(hold.cancelled_appointment, id);
+
+        // Held slot is hidden from others and cannot be taken by them
+        let free = client.get_free_slots(&doctor, &datetime, &(datetime + 3 * SLOT));
+        assert_eq!(free.get(0).unwrap().start, datetime);
+        assert_eq!(free.len(), 1);
+        let result = client.try_create_appointment(&patient2, &doctor, &hold.slot.start);
+        assert_eq!(result, Err(Ok(Error::SlotHeld.into())));
+
+        // The patient claims it, which releases the hold
+        client.create_appointment(&patient, &doctor, &hold.slot.start);
+        assert_eq!(client.get_slot_holds(&doctor).len(), 0);
+    }
+
+    #[test]
+    fn test_institution_can_cancel_for_doctor() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let institution = doctor_institution(&env, &client, &doctor);
+
+        let id = client.create_appointment(&patient, &doctor, &1640995200);
+        client.cancel_appointment_by_provider(
+            &institution,
+            &id,
+            &CancellationReason::FacilityClosure,
+        );
+
+        let record = client.get_cancellation(&id).unwrap();
+        assert_eq!(record.cancelled_by, institution);
+        assert_eq!(record.reason, CancellationReason::FacilityClosure);
+    }
+
+    #[test]
+    fn test_suspended_institution_cannot_act_for_doctor() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let institution = doctor_institution(&env, &client, &doctor);
+        let id = client.create_appointment(&patient, &doctor, &1640995200);
+        env.ledger().set_timestamp(1640995200);
+
+        let registry =
+            HealthcareRegistryClient::new(&env, &client.get_config().healthcare_registry);
+        let admin = client.get_config().admin;
+        let reason = String::from_str(&env, "Inspection pending");
+        registry.suspend_institution(&admin, &institution, &reason);
+
+        let result = client.try_cancel_appointment_by_provider(
+            &institution,
+            &id,
+            &CancellationReason::FacilityClosure,
+        );
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+        let result = client.try_check_in(&institution, &id);
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+
+        registry.reinstate_institution(&admin, &institution, &reason);
+        client.check_in(&institution, &id);
+    }
+
+    #[test]
+    fn test_unrelated_party_cannot_cancel_as_provider() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let stranger = Address::generate(&env);
+        env.mock_all_auths();
+
+        let id = client.create_appointment(&patient, &doctor, &1640995200);
+
+        let result =
+            client.try_cancel_appointment_by_provider(&stranger, &id, &CancellationReason::Other);
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+        let result =
+            client.try_cancel_appointment_by_provider(&patient, &id, &CancellationReason::Other);
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+    }
+
+    #[test]
+    fn test_rebooking_hold_expires() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        env.ledger().set_timestamp(datetime - 7 * 86400);
+        let id = client.create_appointment(&patient, &doctor, &datetime);
+        client.cancel_appointment_by_provider(&doctor, &id, &CancellationReason::Emergency);
+        let hold = client.get_slot_holds(&doctor).get(0).unwrap();
+        assert_eq!(hold.expires_at, datetime - 5 * 86400);
+
+        env.ledger().set_timestamp(hold.expires_at);
+        assert_eq!(client.get_slot_holds(&doctor).len(), 0);
+        client.create_appointment(&patient2, &doctor, &hold.slot.start);
+    }
+
+    #[test]
+    fn test_double_initialize_fails() {
+        let env = Env::default();
+        let (client, _, _) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let config = client.get_config();
+        let result = client.try_initialize(
+            &config.admin,
+            &config.healthcare_registry,
+            &config.doctor_registry,
+        );
+        assert_eq!(result, Err(Ok(Error::AlreadyInitialized.into())));
+    }
+
+    // Appointment Query Tests
+    #[test]
+    fn test_query_appointments_filters() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        let id1 = client.create_appointment(&patient, &doctor, &day);
+        let id2 = client.create_appointment(&patient, &doctor, &(day + SLOT));
+        let id3 = client.create_appointment(&patient2, &doctor, &(day + 86400));
+        client.cancel_appointment(&patient, &id2);
+
+        // Role: patient2 appears only as a patient
+        let mut filter = AppointmentFilter {
+            role: AppointmentRole::Doctor,
+            statuses: Vec::new(&env),
+            from: None,
+            to: None,
+        };
+        let page = client.query_appointments(&doctor, &filter, &0, &10);
+        assert_eq!(page.appointments.len(), 3);
+        assert_eq!(page.next_cursor, None);
+        let page = client.query_appointments(&patient2, &filter, &0, &10);
+        assert_eq!(page.appointments.len(), 0);
+
+        // Status
+        filter.statuses = vec![&env, AppointmentStatus::Scheduled];
+        let page = client.query_appointments(&doctor, &filter, &0, &10);
+        assert_eq!(page.appointments.len(), 2);
+        assert_eq!(page.appointments.get(0).unwrap().id, id1);
+        assert_eq!(page.appointments.get(1).unwrap().id, id3);
+
+        // Datetime range
+        filter.statuses = Vec::new(&env);
+        filter.from = Some(day + SLOT);
+        filter.to = Some(day + 86400);
+        let page = client.query_appointments(&doctor, &filter, &0, &10);
+        assert_eq!(page.appointments.len(), 1);
+        assert_eq!(page.appointments.get(0).unwrap().id, id2);
+
+        filter.role = AppointmentRole::Patient;
+        filter.from = None;
+        filter.to = None;
+        let page = client.query_appointments(&patient, &filter, &0, &10);
+        assert_eq!(page.appointments.len(), 2);
+    }
+
+    #[test]
+    fn test_query_appointments_pagination() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        for i in 0..5 {
+            client.create_appointment(&patient, &doctor, &(day + i * SLOT));
+        }
+
+        let filter = AppointmentFilter {
+            role: AppointmentRole::Patient,
+            statuses: Vec::new(&env),
+            from: None,
+            to: None,
+        };
+        let page = client.query_appointments(&patient, &filter, &0, &2);
+        assert_eq!(page.appointments.len(), 2);
+        assert_eq!(page.next_cursor, Some(2));
+
+        let page = client.query_appointments(&patient, &filter, &2, &2);
+        assert_eq!(page.appointments.get(0).unwrap().id, 3);
+        assert_eq!(page.next_cursor, Some(4));
+
+        let page = client.query_appointments(&patient, &filter, &4, &2);
+        assert_eq!(page.appointments.len(), 1);
+        assert_eq!(page.next_cursor, None);
+
+        let result = client.try_query_appointments(&patient, &filter, &0, &0);
+        assert_eq!(result, Err(Ok(Error::InvalidPageSize.into())));
+    }
+
+    #[test]
+    fn test_migrate_legacy_user_appointments() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+
+        // Appointments as stored before durations, the paged index and per-day filing existed
+        env.as_contract(&client.address, || {
+            let storage = env.storage().persistent();
+            for (id, datetime, status) in [
+                (1u64, day, AppointmentStatus::Scheduled),
+                (2u64, day + SLOT, AppointmentStatus::Scheduled),
+                (3u64, day + 2 * SLOT, AppointmentStatus::Canceled),
+            ] {
+                let legacy = LegacyAppointment {
+                    id,
+                    patient: patient.clone(),
+                    doctor: doctor.clone(),
+                    datetime,
+                    status,
+                };
+                storage.set(&AppointmentKey::Appointment(id), &legacy);
+            }
+            storage.set(&AppointmentKey::AppointmentCounter, &3u64);
+            for user in [&patient, &doctor] {
+                storage.set(
+                    &AppointmentKey::UserAppointments(user.clone()),
+                    &vec![&env, 1u64, 2u64, 3u64],
+                );
+            }
+        });
+
+        // Booked after the upgrade but before the migration ran
+        let booked = client.create_appointment(&patient, &doctor, &(day + 3 * SLOT));
+        assert_eq!(booked, 4);
+
+        let filter = AppointmentFilter {
+            role: AppointmentRole::Patient,
+            statuses: Vec::new(&env),
+            from: None,
+            to: None,
+        };
+        assert_eq!(client.get_appointments(&patient).len(), 4);
+        assert_eq!(
+            client
+                .query_appointments(&patient, &filter, &0, &10)
+                .appointments
+                .len(),
+            1
+        );
+
+        assert_eq!(client.migrate_user_appointments(&patient, &1), 2);
+        assert_eq!(client.migrate_user_appointments(&patient, &10), 0);
+        assert_eq!(client.migrate_user_appointments(&patient, &10), 0);
+
+        // Migrated appointments follow the ones already in the index
+        let page = client.query_appointments(&patient, &filter, &0, &10);
+        let mut ids = Vec::new(&env);
+        for appointment in page.appointments.iter() {
+            ids.push_back(appointment.id);
+        }
+        assert_eq!(ids, vec![&env, 4u64, 1u64, 2u64, 3u64]);
+        assert_eq!(client.get_appointments(&patient).len(), 4);
+
+        // Migrated bookings hold their slots again; the cancelled one frees its slot
+        let result = client.try_create_appointment(&patient, &doctor, &day);
+        assert_eq!(result, Err(Ok(Error::SlotAlreadyBooked.into())));
+        client.create_appointment(&patient, &doctor, &(day + 2 * SLOT));
+
+        // Migrating the doctor's copy of the same ids files nothing twice
+        assert_eq!(client.migrate_user_appointments(&doctor, &10), 0);
+        env.as_contract(&client.address, || {
+            let filed: Vec<u64> = env
+                .storage()
+                .persistent()
+                .get(&AppointmentKey::DoctorDay(doctor.clone(), day / 86400))
+                .unwrap();
+            assert_eq!(filed, vec![&env, 4u64, 1u64, 2u64, 5u64]);
+        });
+
+        // Migrated appointments are stored in the current layout with a default duration
+        env.as_contract(&client.address, || {
+            let stored: Appointment = env
+                .storage()
+                .persistent()
+                .get(&AppointmentKey::Appointment(2))
+                .unwrap();
+            assert_eq!(stored.duration, 30 * 60);
+            assert_eq!(stored.series_id, None);
+        });
+    }
+
+    // Provider Verification Tests
+    #[test]
+    fn test_unregistered_doctor_cannot_be_booked() {
+        let env = Env::default();
+        let (client, patient, _) = setup_appointment_test(&env);
+        let impostor = Address::generate(&env);
+        env.mock_all_auths();
+
+        let result = client.try_create_appointment(&patient, &impostor, &1640995200);
+        assert_eq!(result, Err(Ok(Error::DoctorNotRegistered.into())));
+    }
+
+    #[test]
+    fn test_doctor_of_unverified_institution_cannot_be_booked() {
+        let env = Env::default();
+        let (client, patient, _) = setup_appointment_test(&env);
+        let doctor = Address::generate(&env);
+        let institution = Address::generate(&env);
+        env.mock_all_auths();
+
+        let registry =
+            HealthcareRegistryClient::new(&env, &client.get_config().healthcare_registry);
+        let name = String::from_str(&env, "Pending Clinic");
+        registry.register_institution(&institution, &name, &name, &name);
+        register_doctor(&env, &client, &doctor, &institution);
+
+        let result = client.try_create_appointment(&patient, &doctor, &1640995200);
+        assert_eq!(result, Err(Ok(Error::InstitutionNotVerified.into())));
+
+        // Unknown institution wallet
+        let doctor2 = Address::generate(&env);
+        register_doctor(&env, &client, &doctor2, &Address::generate(&env));
+        let result = client.try_create_appointment(&patient, &doctor2, &1640995200);
+        assert_eq!(result, Err(Ok(Error::InstitutionNotVerified.into())));
+    }
+
+    #[test]
+    fn test_booking_requires_initialized_registries() {
+        let env = Env::default();
+        let contract_id = env.register(AppointmentScheduling, ());
+        let client = AppointmentSchedulingClient::new(&env, &contract_id);
+        env.mock_all_auths();
+
+        let result = client.try_create_appointment(
+            &Address::generate(&env),
+            &Address::generate(&env),
+            &1640995200,
+        );
+        assert_eq!(result, Err(Ok(Error::NotInitialized.into())));
+    }
+
+    // Waitlist Tests
+    #[test]
+    fn test_join_and_leave_waitlist() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        client.join_waitlist(&patient, &doctor, &day, &(day + 86400));
+        let waitlist = client.get_waitlist(&doctor);
+        assert_eq!(waitlist.len(), 1);
+        assert_eq!(waitlist.get(0).unwrap().patient, patient);
+
+        let result = client.try_join_waitlist(&patient, &doctor, &day, &(day + 86400));
+        assert_eq!(result, Err(Ok(Error::AlreadyOnWaitlist.into())));
+
+        client.leave_waitlist(&patient, &doctor);
+        assert_eq!(client.get_waitlist(&doctor).len(), 0);
+        let result = client.try_leave_waitlist(&patient, &doctor);
+        assert_eq!(result, Err(Ok(Error::NotOnWaitlist.into())));
+    }
+
+    #[test]
+    fn test_cancellation_offers_slot_to_waitlist() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let early_bird = Address::generate(&env);
+        let waiting = Address::generate(&env);
+        let walk_in = Address::generate(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &(day + SLOT));
+
+        // First in line wants an evening slot, so the morning one skips them
+        client.join_waitlist(&early_bird, &doctor, &(day + 12 * 3600), &(day + 86400));
+        client.join_waitlist(&waiting, &doctor, &day, &(day + 3600));
+
+        client.cancel_appointment(&patient, &id);
+
+        let holds = client.get_slot_holds(&doctor);
+        assert_eq!(holds.len(), 1);
+        let offer = holds.get(0).unwrap();
+        assert_eq!(offer.kind, HoldKind::WaitlistOffer);
+        assert_eq!(offer.patient, waiting);
+        assert_eq!(offer.slot.start, day + SLOT);
+        assert_eq!(offer.cancelled_appointment, id);
+
+        let result = client.try_create_appointment(&walk_in, &doctor, &(day + SLOT));
+        assert_eq!(result, Err(Ok(Error::SlotHeld.into())));
+
+        // Claiming books the slot and leaves the waitlist
+        client.create_appointment(&waiting, &doctor, &(day + SLOT));
+        assert_eq!(client.get_slot_holds(&doctor).len(), 0);
+        let waitlist = client.get_waitlist(&doctor);
+        assert_eq!(waitlist.len(), 1);
+        assert_eq!(waitlist.get(0).unwrap().patient, early_bird);
+    }
+
+    #[test]
+    fn test_lapsed_offer_moves_to_next_patient() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        env.ledger().set_timestamp(day - 86400);
+        let id = client.create_appointment(&patient, &doctor, &day);
+        client.join_waitlist(&first, &doctor, &day, &(day + 86400));
+        client.join_waitlist(&second, &doctor, &day, &(day + 86400));
+
+        client.cancel_appointment(&patient, &id);
+        let offer = client.get_slot_holds(&doctor).get(0).unwrap();
+        assert_eq!(offer.patient, first);
+
+        env.ledger().set_timestamp(offer.expires_at);
+        client.refresh_waitlist_offers(&doctor);
+
+        let offer = client.get_slot_holds(&doctor).get(0).unwrap();
+        assert_eq!(offer.patient, second);
+        assert_eq!(offer.passed_over, vec![&env, first.clone()]);
+
+        // The lapsed patient keeps their place for future slots but cannot take this one
+        assert_eq!(client.get_waitlist(&doctor).len(), 2);
+        let result = client.try_create_appointment(&first, &doctor, &day);
+        assert_eq!(result, Err(Ok(Error::SlotHeld.into())));
+    }
+
+    #[test]
+    fn test_declined_offer_moves_to_next_patient() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+        env.mock_all_auths();
+
+        let day = 1640995200;
+        let id = client.create_appointment(&patient, &doctor, &day);
+        client.join_waitlist(&first, &doctor, &day, &(day + 86400));
+        client.join_waitlist(&second, &doctor, &day, &(day + 86400));
+        client.cancel_appointment(&patient, &id);
+
+        let result = client.try_decline_waitlist_offer(&second, &doctor, &day);
+        assert_eq!(result, Err(Ok(Error::OfferNotFound.into())));
+
+        client.decline_waitlist_offer(&first, &doctor, &day);
+        let holds = client.get_slot_holds(&doctor);
+        assert_eq!(holds.len(), 1);
+        assert_eq!(holds.get(0).unwrap().patient, second);
+
+        client.create_appointment(&second, &doctor, &day);
+    }
+
+    // Recurring Series Tests
+    const WEEK: u64 = 7 * 86400;
+
+    #[test]
+    fn test_create_weekly_series() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let first = 1640995200;
+        let rule = RecurrenceRule {
+            interval: WEEK,
+            count: 4,
+            until: 0,
+        };
+        let result = client.create_appointment_series(&patient, &doctor, &first, &rule);
+        assert_eq!(result.booked.len(), 4);
+        assert_eq!(result.conflicts.len(), 0);
+
+        let series = client.get_series(&result.series_id);
+        assert_eq!(series.appointment_ids, result.booked);
+        assert_eq!(series.rule, rule);
+
+        let appointments = client.get_appointments(&patient);
+        assert_eq!(appointments.len(), 4);
+        for (i, appointment) in appointments.iter().enumerate() {
+            assert_eq!(appointment.datetime, first + i as u64 * WEEK);
+            assert_eq!(appointment.series_id, Some(result.series_id));
+        }
+    }
+
+    #[test]
+    fn test_series_reports_conflicts_per_occurrence() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let other = Address::generate(&env);
+        env.mock_all_auths();
+
+        let first = 1640995200;
+        client.create_appointment(&other, &doctor, &(first + WEEK));
+        client.add_availability_exception(
+            &doctor,
+            &AvailabilityException {
+                start: first + 2 * WEEK,
+                end: first + 2 * WEEK + 86400,
+                available: false,
+            },
+        );
+
+        // Ends by date: four weekly occurrences fit before `until`
+        let rule = RecurrenceRule {
+            interval: WEEK,
+            count: 0,
+            until: first + 3 * WEEK,
+        };
+        let result = client.create_appointment_series(&patient, &doctor, &first, &rule);
+        assert_eq!(result.booked.len(), 2);
+        assert_eq!(
+            result.conflicts,
+            vec![
+                &env,
+                SeriesConflict {
+                    datetime: first + WEEK,
+                    reason: ConflictReason::SlotAlreadyBooked,
+                },
+                SeriesConflict {
+                    datetime: first + 2 * WEEK,
+                    reason: ConflictReason::SlotUnavailable,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_series_reports_unaffordable_deposits() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        // 1000 minted covers ten 100 deposits; the rest are reported, not rolled back
+        let token = setup_deposit(&env, &client, &doctor, &patient);
+        let first = 1640995200;
+        let rule = RecurrenceRule {
+            interval: WEEK,
+            count: 12,
+            until: 0,
+        };
+        let result = client.create_appointment_series(&patient, &doctor, &first, &rule);
+        assert_eq!(result.booked.len(), 10);
+        assert_eq!(
+            result.conflicts,
+            vec![
+                &env,
+                SeriesConflict {
+                    datetime: first + 10 * WEEK,
+                    reason: ConflictReason::InsufficientDeposit,
+                },
+                SeriesConflict {
+                    datetime: first + 11 * WEEK,
+                    reason: ConflictReason::InsufficientDeposit,
+                },
+            ]
+        );
+        assert_eq!(token.balance(&patient), 0);
+    }
+
+    #[test]
+    fn test_open_ended_series_stops_at_cap() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let first = 1640995200;
+        let rule = RecurrenceRule {
+            interval: WEEK,
+            count: 0,
+            until: first + 200 * WEEK,
+        };
+        let result = client.create_appointment_series(&patient, &doctor, &first, &rule);
+        assert_eq!(result.booked.len(), 52);
+        assert_eq!(
+            client.get_appointments(&patient).last().unwrap().datetime,
+            first + 51 * WEEK
+        );
+    }
+
+    #[test]
+    fn test_series_stops_before_time_overflows() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let first = 1640995200;
+        let rule = RecurrenceRule {
+            interval: u64::MAX / 2,
+            count: 3,
+            until: 0,
+        };
+        let result = client.create_appointment_series(&patient, &doctor, &first, &rule);
+        assert_eq!(result.booked.len(), 1);
+        assert_eq!(result.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn test_cancel_single_occurrence_and_remaining_series() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let first = 1640995200;
+        let rule = RecurrenceRule {
+            interval: WEEK,
+            count: 4,
+            until: 0,
+        };
+        let result = client.create_appointment_series(&patient, &doctor, &first, &rule);
+        let ids = result.booked;
+
+        // One occurrence cancelled on its own, the first one attended
+        client.cancel_appointment(&patient, &ids.get(1).unwrap());
+        env.ledger().set_timestamp(first + SLOT);
+        client.complete_appointment(&doctor, &ids.get(0).unwrap());
+
+        assert_eq!(client.cancel_series(&patient, &result.series_id), 2);
+
+        let appointments = client.get_appointments(&patient);
+        assert_eq!(
+            appointments.get(0).unwrap().status,
+            AppointmentStatus::Completed
+        );
+        for i in 1..4 {
+            assert_eq!(
+                appointments.get(i).unwrap().status,
+                AppointmentStatus::Canceled
+            );
+        }
+
+        let stranger = Address::generate(&env);
+        let result = client.try_cancel_series(&stranger, &result.series_id);
+        assert_eq!(result, Err(Ok(Error::UnauthorizedAppointmentAction.into())));
+    }
+
+    #[test]
+    fn test_invalid_recurrence_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let first = 1640995200;
+        let unbounded = RecurrenceRule {
+            interval: WEEK,
+            count: 0,
+            until: 0,
+        };
+        let result = client.try_create_appointment_series(&patient, &doctor, &first, &unbounded);
+        assert_eq!(result, Err(Ok(Error::InvalidRecurrence.into())));
+
+        let too_long = RecurrenceRule {
+            interval: WEEK,
+            count: 53,
+            until: 0,
+        };
+        let result = client.try_create_appointment_series(&patient, &doctor, &first, &too_long);
+        assert_eq!(result, Err(Ok(Error::InvalidRecurrence.into())));
+    }
+
+    #[test]
+    fn test_check_in_and_wait_metrics() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let first = client.create_appointment(&patient, &doctor, &datetime);
+        let second = client.create_appointment(&patient, &doctor, &(datetime + SLOT));
+
+        // Patient checks in early, doctor starts ten minutes later, visit lasts 20 minutes
+        env.ledger().set_timestamp(datetime - 600);
+        client.check_in(&patient, &first);
+        assert_eq!(
+            client.get_appointments(&doctor).get(0).unwrap().status,
+            AppointmentStatus::CheckedIn
+        );
+        env.ledger().set_timestamp(datetime);
+        client.start_appointment(&doctor, &first);
+        assert_eq!(
+            client.get_appointments(&doctor).get(0).unwrap().status,
+            AppointmentStatus::InProgress
+        );
+        env.ledger().set_timestamp(datetime + 1200);
+        client.complete_appointment(&doctor, &first);
+
+        let timeline = client.get_timeline(&first);
+        assert_eq!(timeline.checked_in_at, Some(datetime - 600));
+        assert_eq!(timeline.started_at, Some(datetime));
+        assert_eq!(timeline.completed_at, Some(datetime + 1200));
+
+        // Front desk checks in the second visit, which waits 20 minutes and lasts 10
+        let institution = doctor_institution(&env, &client, &doctor);
+        client.check_in(&institution, &second);
+        env.ledger().set_timestamp(datetime + 2400);
+        client.start_appointment(&doctor, &second);
+        env.ledger().set_timestamp(datetime + 3000);
+        client.complete_appointment(&doctor, &second);
+
+        let metrics = client.get_wait_metrics(&doctor);
+        assert_eq!(metrics.waits_recorded, 2);
+        assert_eq!(metrics.total_wait, 600 + 1200);
+        assert_eq!(metrics.average_wait, 900);
+        assert_eq!(metrics.consultations_recorded, 2);
+        assert_eq!(metrics.average_consultation, 900);
+    }
+
+    #[test]
+    fn test_check_in_requires_patient_or_institution() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
+        env.ledger().set_timestamp(datetime);
+
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            client.try_check_in(&stranger, &appointment_id),
+            Err(Ok(Error::UnauthorizedAppointmentAction.into()))
+        );
+
+        client.check_in(&patient, &appointment_id);
+        assert_eq!(
+            client.try_check_in(&patient, &appointment_id),
+            Err(Ok(Error::InvalidAppointmentStatus.into()))
+        );
+    }
+
+    #[test]
+    fn test_check_in_only_around_appointment_time() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
+
+        // Days ahead is too early
+        env.ledger().set_timestamp(datetime - 86400);
+        assert_eq!(
+            client.try_check_in(&patient, &appointment_id),
+            Err(Ok(Error::OutsideCheckInWindow.into()))
+        );
+
+        // Once the slot has ended it is too late
+        env.ledger().set_timestamp(datetime + SLOT);
+        assert_eq!(
+            client.try_check_in(&patient, &appointment_id),
+            Err(Ok(Error::OutsideCheckInWindow.into()))
+        );
+
+        env.ledger().set_timestamp(datetime - 2 * 3600);
+        client.check_in(&patient, &appointment_id);
+    }
+
+    #[test]
+    fn test_checked_in_appointment_keeps_slot() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let datetime = 1640995200;
+        let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
+        env.ledger().set_timestamp(datetime - 600);
+        client.check_in(&patient, &appointment_id);
+
+        let other = Address::generate(&env);
+        assert_eq!(
+            client.try_create_appointment(&other, &doctor, &datetime),
+            Err(Ok(Error::SlotAlreadyBooked.into()))
+        );
+
+        // Completing without a start leaves no consultation sample
+        client.complete_appointment(&doctor, &appointment_id);
+        assert_eq!(client.get_wait_metrics(&doctor).consultations_recorded, 0);
+    }
+
+    #[test]
+    fn test_institution_suspension_and_reinstatement() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        assert_eq!(
+            client.get_institution(&inst_addr).status,
+            InstitutionStatus::Pending
+        );
+
+        env.ledger().set_timestamp(1_000);
+        client.verify_institution(&admin, &inst_addr);
+
+        env.ledger().set_timestamp(2_000);
+        let reason = String::from_str(&env, "Inspection pending");
+        client.suspend_institution(&admin, &inst_addr, &reason);
+
+        let data = client.get_institution(&inst_addr);
+        assert_eq!(data.status, InstitutionStatus::Suspended);
+        assert!(!data.is_verified);
+
+        env.ledger().set_timestamp(3_000);
+        client.reinstate_institution(
+            &admin,
+            &inst_addr,
+            &String::from_str(&env, "Inspection passed"),
+        );
+
+        let data = client.get_institution(&inst_addr);
+        assert_eq!(data.status, InstitutionStatus::Verified);
+        assert!(data.is_verified);
+        assert_eq!(data.status_history.len(), 3);
+
+        let suspension = data.status_history.get(1).unwrap();
+        assert_eq!(suspension.status, InstitutionStatus::Suspended);
+        assert_eq!(suspension.reason, reason);
+        assert_eq!(suspension.changed_by, admin);
+        assert_eq!(suspension.changed_at, 2_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only suspended institutions can be reinstated")]
+    fn test_revoked_institution_cannot_be_reinstated() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.verify_institution(&admin, &inst_addr);
+
+        let reason = String::from_str(&env, "License fraud");
+        client.revoke_institution(&admin, &inst_addr, &reason);
+        assert!(!client.get_institution(&inst_addr).is_verified);
+
+        client.reinstate_institution(&admin, &inst_addr, &reason);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not authorized to verify")]
+    fn test_unauthorized_suspension_fails() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.verify_institution(&admin, &inst_addr);
+
+        client.suspend_institution(&inst_addr, &inst_addr, &name);
+    }
+
+    #[test]
+    fn test_suspended_institution_blocks_new_bookings() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let registry =
+            HealthcareRegistryClient::new(&env, &client.get_config().healthcare_registry);
+        let admin = client.get_config().admin;
+        let institution = doctor_institution(&env, &client, &doctor);
+        let reason = String::from_str(&env, "Inspection pending");
+        registry.suspend_institution(&admin, &institution, &reason);
+
+        let result = client.try_create_appointment(&patient, &doctor, &1640995200);
+        assert_eq!(result, Err(Ok(Error::InstitutionNotVerified.into())));
+
+        registry.reinstate_institution(&admin, &institution, &reason);
+        client.create_appointment(&patient, &doctor, &1640995200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Already initialized")]
+    fn test_init_only_once() {
+        let env = Env::default();
+        let (client, _, _) = setup_test(&env);
+
+        client.init(&Address::generate(&env));
+    }
+
+    #[test]
+    fn test_two_step_admin_transfer() {
+        let env = Env::default();
+        let (client, admin, _) = setup_test(&env);
+        let new_admin = Address::generate(&env);
+
+        client.propose_admin(&admin, &new_admin);
+        assert_eq!(client.get_admin(), admin);
+
+        client.accept_admin(&new_admin);
+        assert_eq!(client.get_admin(), new_admin);
+
+        // The old admin no longer manages the verifier set
+        let result = client.try_add_verifier(&admin, &Address::generate(&env));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not the pending admin")]
+    fn test_admin_transfer_requires_proposed_address() {
+        let env = Env::default();
+        let (client, admin, _) = setup_test(&env);
+
+        client.propose_admin(&admin, &Address::generate(&env));
+        client.accept_admin(&Address::generate(&env));
+    }
+
+    #[test]
+    fn test_verification_requires_threshold_of_verifiers() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        let verifier_a = Address::generate(&env);
+        let verifier_b = Address::generate(&env);
+
+        client.add_verifier(&admin, &verifier_a);
+        client.add_verifier(&admin, &verifier_b);
+        client.set_verification_threshold(&admin, &2);
+        assert_eq!(client.get_verifiers().len(), 3);
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+
+        client.verify_institution(&verifier_a, &inst_addr);
+        assert!(!client.get_institution(&inst_addr).is_verified);
+        assert_eq!(
+            client.get_approvals(&inst_addr),
+            vec![&env, verifier_a.clone()]
+        );
+
+        client.verify_institution(&verifier_b, &inst_addr);
+        assert!(client.get_institution(&inst_addr).is_verified);
+        assert_eq!(client.get_approvals(&inst_addr).len(), 0);
+    }
+
+    #[test]
+    fn test_removed_verifier_approval_does_not_count() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        let verifier_a = Address::generate(&env);
+        let verifier_b = Address::generate(&env);
+
+        client.add_verifier(&admin, &verifier_a);
+        client.add_verifier(&admin, &verifier_b);
+        client.set_verification_threshold(&admin, &2);
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.verify_institution(&verifier_a, &inst_addr);
+
+        client.remove_verifier(&admin, &verifier_a);
+        client.verify_institution(&verifier_b, &inst_addr);
+        assert!(!client.get_institution(&inst_addr).is_verified);
+
+        client.verify_institution(&admin, &inst_addr);
+        assert!(client.get_institution(&inst_addr).is_verified);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid threshold")]
+    fn test_threshold_cannot_exceed_verifier_count() {
+        let env = Env::default();
+        let (client, admin, _) = setup_test(&env);
+
+        client.set_verification_threshold(&admin, &2);
+    }
+
+    #[test]
+    fn test_license_expiry_lapses_verification() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.set_license_details(
+            &inst_addr,
+            &String::from_str(&env, "State Medical Board"),
+            &0,
+            &(10 * 86400),
+        );
+        client.verify_institution(&admin, &inst_addr);
+        assert!(client.get_institution(&inst_addr).is_verified);
+
+        env.ledger().set_timestamp(10 * 86400);
+        let data = client.get_institution(&inst_addr);
+        assert!(!data.is_verified);
+        assert_eq!(data.status, InstitutionStatus::Expired);
+
+        // Renewal restores verification once a verifier approves the evidence
+        let evidence = BytesN::from_array(&env, &[7; 32]);
+        client.submit_license_renewal(&inst_addr, &evidence, &(400 * 86400));
+        assert!(!client.get_institution(&inst_addr).is_verified);
+        assert_eq!(
+            client
+                .get_license_renewal(&inst_addr)
+                .unwrap()
+                .evidence_hash,
+            evidence
+        );
+
+        client.approve_license_renewal(&admin, &inst_addr);
+        let data = client.get_institution(&inst_addr);
+        assert!(data.is_verified);
+        assert_eq!(data.license_expires_at, 400 * 86400);
+        assert_eq!(client.get_license_renewal(&inst_addr), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Institution is suspended or revoked")]
+    fn test_suspended_institution_cannot_submit_renewal() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.set_license_details(&inst_addr, &name, &0, &(10 * 86400));
+        client.verify_institution(&admin, &inst_addr);
+        client.suspend_institution(&admin, &inst_addr, &name);
+
+        let evidence = BytesN::from_array(&env, &[7; 32]);
+        client.submit_license_renewal(&inst_addr, &evidence, &(400 * 86400));
+    }
+
+    #[test]
+    #[should_panic(expected = "Institution is suspended or revoked")]
+    fn test_renewal_not_approved_after_suspension() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.set_license_details(&inst_addr, &name, &0, &(10 * 86400));
+        client.verify_institution(&admin, &inst_addr);
+
+        let evidence = BytesN::from_array(&env, &[7; 32]);
+        client.submit_license_renewal(&inst_addr, &evidence, &(400 * 86400));
+        client.suspend_institution(&admin, &inst_addr, &name);
+        client.approve_license_renewal(&admin, &inst_addr);
+    }
+
+    #[test]
+    fn test_expiring_institutions_query() {
+        let env = Env::default();
+        let (client, admin, _) = setup_test(&env);
+        env.ledger().set_timestamp(86400);
+
+        let name = String::from_str(&env, "Clinic");
+        let soon = Address::generate(&env);
+        let later = Address::generate(&env);
+        let pending = Address::generate(&env);
+        let revoked = Address::generate(&env);
+        for (wallet, expires_at) in [
+            (&soon, 5 * 86400),
+            (&later, 60 * 86400),
+            (&pending, 5 * 86400),
+            (&revoked, 5 * 86400),
+        ] {
+            client.register_institution(wallet, &name, &name, &name);
+            client.set_license_details(wallet, &name, &0, &expires_at);
+        }
+
+        // Only institutions that are currently verified have a verification to lose
+        client.verify_institution(&admin, &soon);
+        client.verify_institution(&admin, &later);
+        client.verify_institution(&admin, &revoked);
+        client.revoke_institution(&admin, &revoked, &name);
+
+        assert_eq!(
+            client.get_expiring_institutions(&(30 * 86400)),
+            vec![&env, soon.clone()]
+        );
+        assert_eq!(client.get_expiring_institutions(&(60 * 86400)).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "License details can only change before verification")]
+    fn test_license_details_locked_after_verification() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+
+        let name = String::from_str(&env, "Clinic A");
+        client.register_institution(&inst_addr, &name, &name, &name);
+        client.verify_institution(&admin, &inst_addr);
+
+        client.set_license_details(&inst_addr, &name, &0, &(365 * 86400));
+    }
 }